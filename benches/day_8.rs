@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use aoc_2022::task_solver::day_8::Forest;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+/// forest of pseudo-random heights - a linear congruential generator keeps it reproducible
+fn generate_forest(width: usize, height: usize, seed: u64) -> Forest {
+    let mut state = seed;
+    let rows = (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    ((state >> 33) % 10) as u8
+                })
+                .collect()
+        })
+        .collect();
+    Forest::from_rows(rows).expect("generated rows are equally wide")
+}
+
+fn sweep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day_8");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+
+    let forest = generate_forest(5000, 5000, 8);
+    group.bench_function("sweep 5000x5000", |b| b.iter(|| forest.sweep()));
+    group.bench_function("top spots 5000x5000", |b| {
+        b.iter_batched(
+            || &forest,
+            |forest| forest.top_spots(10),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sweep);
+criterion_main!(benches);
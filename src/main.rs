@@ -2,7 +2,7 @@ use anyhow::Result;
 use clap::{arg, command, Parser};
 use log::info;
 
-mod task_solver;
+use aoc_2022::task_solver;
 
 /// Program to compute solution of AOC tasks
 #[derive(Parser, Debug)]
@@ -11,7 +11,7 @@ struct Args {
     /// Day in the advent of code calendar
     #[arg(short, long)]
     day: u8,
-    /// Task number on that day (either 1 or 2)
+    /// Task number on that day (either 1 or 2 - day 1 also has 3, a report of the inventory)
     #[arg(short, long)]
     task: u8,
     /// Path to the input file
@@ -28,5 +28,8 @@ fn main() -> Result<()> {
         args.day, args.task, args.input
     );
 
-    task_solver::solve_task(args.day, args.task, args.input)
+    let answer = task_solver::solve_task(args.day, args.task, args.input)?;
+    println!("{}", answer);
+
+    Ok(())
 }
@@ -1,101 +1,314 @@
 use anyhow::{anyhow, Context, Result};
 use log::{debug, info};
-use std::{
-    cmp,
-    fs::File,
-    io::{BufRead, BufReader},
+use std::{collections::BTreeMap, fs::File, io::BufReader};
+
+use crate::task_solver::util::{
+    input::{self, Paragraphs},
+    top_k::TopK,
 };
 
-use crate::task_solver::util;
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let inventory = Inventory::init(input).context("Failed to instantiate inventory")?;
+    debug!("{}", inventory.summary());
 
-pub fn solve(task: u8, input: String) -> Result<()> {
     match task {
-        1 => solve_1(input),
-        2 => solve_2(input),
-        _ => Err(anyhow!("This task doesn't exist - choose one of 1 or 2.")),
+        1 => solve_1(&inventory),
+        2 => solve_2(&inventory),
+        3 => Ok(inventory.report(3, 10000, inventory.half_of_all_calories())),
+        _ => Err(anyhow!(
+            "This task doesn't exist - choose one of 1 or 2, or 3 for a report of the inventory."
+        )),
     }
 }
 
-fn solve_1(input: String) -> Result<()> {
-    // instantiate parser
-    let parser = ElfParser::init(input).context("Failed to instantiate parser")?;
+fn solve_1(inventory: &Inventory) -> Result<String> {
+    let (elf, max_cals) = inventory
+        .top(1)
+        .pop()
+        .ok_or(anyhow!("inventory doesn't contain any elves"))?;
+
+    info!(
+        "Highest number of calories carried by an elf: {} (elf {})",
+        max_cals, elf
+    );
+
+    Ok(max_cals.to_string())
+}
+
+fn solve_2(inventory: &Inventory) -> Result<String> {
+    let top_elves = inventory.top(3);
+    debug!("elves carrying the most calories: {:?}", top_elves);
 
-    let mut max_cals = 0u32;
+    let total = top_elves
+        .iter()
+        .try_fold(0u32, |sum, &(_, cals)| sum.checked_add(cals))
+        .ok_or(anyhow!("calories of the top three elves overflow"))?;
+
+    info!(
+        "Total number of calories carried by the three elves carrying the most calories: {}",
+        total
+    );
+
+    Ok(total.to_string())
+}
 
-    for cur_cals in parser {
-        max_cals = cmp::max(cur_cals, max_cals);
+/// analytics of the inventory in the input: overview, the k elves carrying the most calories,
+/// histogram of the totals and the number of elves needed to carry target calories together
+pub fn report(input: String, k: usize, bucket_width: u32, target: u32) -> Result<String> {
+    if bucket_width == 0 {
+        return Err(anyhow!("buckets have to be at least 1 calorie wide"));
     }
+    let inventory = Inventory::init(input).context("Failed to instantiate inventory")?;
+    Ok(inventory.report(k, bucket_width, target))
+}
 
-    info!("Highest number of calories carried by an elf: {}", max_cals);
+/// The items carried by a single elf
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Elf {
+    /// position of the elf in the input, starting at 0
+    pub index: usize,
+    /// calories of each item
+    pub items: Vec<u32>,
+}
 
-    Ok(())
+impl Elf {
+    pub fn total(&self) -> Result<u32> {
+        self.items
+            .iter()
+            .try_fold(0u32, |sum, &cals| sum.checked_add(cals))
+            .ok_or_else(|| anyhow!("calories carried by elf {} overflow", self.index))
+    }
 }
 
-fn solve_2(input: String) -> Result<()> {
-    // instantiate parser
-    let parser = ElfParser::init(input).context("Failed to instantiate parser")?;
+/// The item lists of all elves, with queries over their calorie totals
+#[derive(Clone, Debug, Default)]
+pub struct Inventory {
+    elves: Vec<Elf>,
+    /// totals of the elves, in the same order as the elves
+    totals: Vec<u32>,
+    /// totals of all elves in ascending order
+    sorted_totals: Vec<u32>,
+}
 
-    let mut sorted_list = util::SortedList::<u32>::new(3);
+impl Inventory {
+    pub fn init(input: String) -> Result<Self> {
+        let elves = ElfParser::init(input)?.collect::<Result<Vec<_>>>()?;
+        Self::from_elves(elves)
+    }
 
-    for elf_cals in parser {
-        debug!("Checking for value {}...", elf_cals);
-        sorted_list.insert(elf_cals);
+    pub fn from_elves(elves: Vec<Elf>) -> Result<Self> {
+        let totals = elves.iter().map(Elf::total).collect::<Result<Vec<_>>>()?;
+        let mut sorted_totals = totals.clone();
+        sorted_totals.sort_unstable();
+        Ok(Inventory {
+            elves,
+            totals,
+            sorted_totals,
+        })
     }
 
-    let total = sorted_list.fold::<u32>(0u32, &|cals, sum| cals + sum);
+    pub fn elves(&self) -> &[Elf] {
+        &self.elves
+    }
 
-    info!(
-        "Total number of calories carried by the three elves carrying the most calories: {}",
-        total
-    );
+    /// indices and totals of the k elves carrying the most calories, largest first
+    pub fn top(&self, k: usize) -> Vec<(usize, u32)> {
+        let mut top = TopK::by_key(k, |&(_, total): &(usize, u32)| total);
+        top.extend(
+            self.elves
+                .iter()
+                .map(|elf| elf.index)
+                .zip(self.totals.iter().copied()),
+        );
+        top.into_sorted_vec()
+    }
+
+    /// median of the totals - the mean of the two middle totals for an even number of elves
+    pub fn median(&self) -> Option<f64> {
+        let n = self.sorted_totals.len();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.sorted_totals[n / 2] as f64),
+            _ => Some(
+                (self.sorted_totals[n / 2 - 1] as f64 + self.sorted_totals[n / 2] as f64) / 2.0,
+            ),
+        }
+    }
 
-    Ok(())
+    /// smallest total such that at least p percent of the elves carry at most that much (nearest rank)
+    pub fn percentile(&self, p: f64) -> Option<u32> {
+        if !(0.0..=100.0).contains(&p) || self.sorted_totals.is_empty() {
+            return None;
+        }
+        let rank = (p / 100.0 * self.sorted_totals.len() as f64).ceil() as usize;
+        Some(self.sorted_totals[rank.saturating_sub(1)])
+    }
+
+    /// number of elves per bucket of totals - keys are the lower bounds of the buckets
+    pub fn histogram(&self, bucket_width: u32) -> BTreeMap<u32, usize> {
+        assert!(
+            bucket_width > 0,
+            "buckets have to be at least 1 calorie wide"
+        );
+        let mut histogram = BTreeMap::new();
+        for total in self.sorted_totals.iter() {
+            *histogram.entry(total - total % bucket_width).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// minimum number of elves that carry at least target calories together - None if all of them
+    /// together don't carry enough
+    pub fn min_elves_for(&self, target: u32) -> Option<usize> {
+        if target == 0 {
+            return Some(0);
+        }
+        // u64, so that the sum of the totals can't overflow
+        let mut carried = 0u64;
+        for (n, &total) in self.sorted_totals.iter().rev().enumerate() {
+            carried += total as u64;
+            if carried >= target as u64 {
+                return Some(n + 1);
+            }
+        }
+        None
+    }
+
+    /// one-line overview of the inventory
+    pub fn summary(&self) -> String {
+        let num_items: usize = self.elves.iter().map(|elf| elf.items.len()).sum();
+        match (self.median(), self.percentile(90.0)) {
+            (Some(median), Some(p90)) => format!(
+                "{} elves carrying {} items - median total: {}, 90th percentile: {}",
+                self.elves.len(),
+                num_items,
+                median,
+                p90
+            ),
+            _ => "inventory is empty".to_owned(),
+        }
+    }
+
+    /// half of the calories carried by all elves together, rounded up
+    pub fn half_of_all_calories(&self) -> u32 {
+        let total: u64 = self.sorted_totals.iter().map(|&total| total as u64).sum();
+        u32::try_from(total.div_ceil(2)).unwrap_or(u32::MAX)
+    }
+
+    /// multi-line report of the inventory - see `report`
+    pub fn report(&self, k: usize, bucket_width: u32, target: u32) -> String {
+        let mut lines = vec![self.summary()];
+        let top: Vec<String> = self
+            .top(k)
+            .iter()
+            .map(|(index, total)| format!("elf {} ({})", index, total))
+            .collect();
+        lines.push(format!("top {}: {}", k, top.join(", ")));
+        lines.push(format!(
+            "histogram of totals per {} calories:",
+            bucket_width
+        ));
+        for (lower, count) in self.histogram(bucket_width) {
+            lines.push(format!("  {:>8}: {}", lower, count));
+        }
+        lines.push(match self.min_elves_for(target) {
+            Some(n) => format!("elves needed to carry {} calories: {}", target, n),
+            None => format!("all elves together carry less than {} calories", target),
+        });
+        lines.join("\n")
+    }
 }
 
-struct ElfParser {
-    in_reader: BufReader<File>,
-    line: String,
+/// Parses the item list of one elf per paragraph of the input
+pub struct ElfParser {
+    paragraphs: Paragraphs<BufReader<File>>,
+    index: usize,
 }
 
 impl ElfParser {
-    fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+    pub fn init(input: String) -> Result<Self> {
+        let paragraphs = input::read_lines(input)?.paragraphs();
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
-
-        Ok(ElfParser { in_reader, line })
+        Ok(ElfParser {
+            paragraphs,
+            index: 0,
+        })
     }
 }
 
 impl Iterator for ElfParser {
-    type Item = u32;
+    type Item = Result<Elf>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut cur_cals = 0u32;
-        loop {
-            let bytes_read = self
-                .in_reader
-                .read_line(&mut self.line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 {
-                return None; // EOF
-            }
+        let paragraph = match self.paragraphs.next()? {
+            Ok(paragraph) => paragraph,
+            Err(e) => return Some(Err(e)),
+        };
+        let index = self.index;
+        self.index += 1;
+        Some(
+            paragraph
+                .iter()
+                .map(|line| line.parse::<u32>())
+                .collect::<Result<_>>()
+                .map(|items| Elf { index, items }),
+        )
+    }
+}
 
-            if self.line == "\n" {
-                break;
-            } else {
-                let cals = self
-                    .line
-                    .trim()
-                    .parse::<u32>()
-                    .expect("Failed to parse int from input");
-                self.line.clear();
-                cur_cals += cals;
-            }
-        }
-        Some(cur_cals)
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::day_1::{Elf, Inventory};
+
+    #[test]
+    fn inventory_test() {
+        let items = [
+            vec![1000, 2000, 3000],
+            vec![4000],
+            vec![5000, 6000],
+            vec![7000, 8000, 9000],
+            vec![10000],
+        ];
+        let inventory = Inventory::from_elves(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, items)| Elf { index, items })
+                .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(inventory.top(2), vec![(3, 24000), (2, 11000)]);
+        assert_eq!(inventory.median(), Some(10000.0));
+        assert_eq!(inventory.percentile(20.0), Some(4000));
+        assert_eq!(inventory.percentile(100.0), Some(24000));
+        assert_eq!(inventory.percentile(101.0), None);
+        assert_eq!(
+            inventory.histogram(10000).into_iter().collect::<Vec<_>>(),
+            vec![(0, 2), (10000, 2), (20000, 1)]
+        );
+        assert_eq!(inventory.min_elves_for(35000), Some(2));
+        assert_eq!(inventory.min_elves_for(55001), None);
+        assert_eq!(Inventory::default().median(), None);
+
+        assert_eq!(
+            inventory.report(1, 10000, 30000),
+            [
+                "5 elves carrying 10 items - median total: 10000, 90th percentile: 24000",
+                "top 1: elf 3 (24000)",
+                "histogram of totals per 10000 calories:",
+                "         0: 2",
+                "     10000: 2",
+                "     20000: 1",
+                "elves needed to carry 30000 calories: 2",
+            ]
+            .join("\n")
+        );
+
+        let overflowing = Elf {
+            index: 7,
+            items: vec![u32::MAX, 1],
+        };
+        assert!(Inventory::from_elves(vec![overflowing]).is_err());
     }
 }
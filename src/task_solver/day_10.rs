@@ -1,133 +1,419 @@
 use anyhow::{anyhow, Context, Result};
-use core::panic;
-use log::{debug, info};
-use regex::Regex;
+use log::{debug, info, log_enabled, Level};
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
 };
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let parser = IParser::init(input).context("failed to instantiate parser")?;
+use super::util::{input, ocr};
+
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let program = Decoder::default()
+        .decode_input(input)
+        .context("failed to decode program")?;
 
     match task {
-        1 => solve_1(parser),
-        2 => solve_2(parser),
+        1 => solve_1(program),
+        2 => ocr::recognize(&draw_crt(program)?),
         _ => Err(anyhow!("task doesn't exist!")),
     }
 }
 
-fn solve_1(parser: IParser) -> Result<()> {
-    let mut regx_sum = 0i32;
-    for (cycle, regx) in parser {
-        if cycle == 20 || (cycle as i32 - 20) % 40 == 0 {
-            regx_sum += regx * cycle as i32;
-            debug!(
-                "cycle {}: adding {} * {} = {}",
-                cycle,
-                regx,
-                cycle,
-                regx * cycle as i32
-            );
-        }
+fn solve_1(program: Program) -> Result<String> {
+    let mut regx_sum = 0i64;
+    {
+        let mut cpu = Cpu::new(program);
+        cpu.on_cycle(|cycle, registers| {
+            if cycle % 40 == 20 {
+                let regx = registers.get("x")?;
+                regx_sum += regx * cycle as i64;
+                debug!(
+                    "cycle {}: adding {} * {} = {}",
+                    cycle,
+                    regx,
+                    cycle,
+                    regx * cycle as i64
+                );
+            }
+            Ok(())
+        });
+        run(cpu)?;
     }
 
     info!("sum of signal strengths: {}", regx_sum);
 
-    Ok(())
+    Ok(regx_sum.to_string())
 }
 
-fn solve_2(parser: IParser) -> Result<()> {
+/// rows of the CRT drawn while running the program, lit pixels are '#'
+pub fn draw_crt(program: Program) -> Result<String> {
+    let mut crt_lines = Vec::new();
     let mut curr_line = String::new();
-    for (cycle, regx) in parser {
-        if ((cycle as i32 - 1) % 40 - regx).abs() <= 1 {
-            curr_line.push('#');
-        } else {
-            curr_line.push('.');
-        }
-        if cycle % 40 == 0 {
-            info!("{}", curr_line);
-            curr_line.clear();
-        }
+    {
+        let mut cpu = Cpu::new(program);
+        cpu.on_cycle(|cycle, registers| {
+            let pixel = (cycle as i64 - 1) % 40;
+            if (pixel - registers.get("x")?).abs() <= 1 {
+                curr_line.push('#');
+            } else {
+                curr_line.push('.');
+            }
+            if cycle % 40 == 0 {
+                info!("{}", curr_line);
+                crt_lines.push(std::mem::take(&mut curr_line));
+            }
+            Ok(())
+        });
+        run(cpu)?;
     }
 
+    Ok(crt_lines.join("\n"))
+}
+
+/// runs the program to the end - with debug logging, the trace of every cycle is dumped
+fn run(mut cpu: Cpu) -> Result<()> {
+    if log_enabled!(Level::Debug) {
+        cpu.enable_trace();
+    }
+    cpu.run()?;
+    if log_enabled!(Level::Debug) {
+        debug!("trace:\n{}", cpu.trace_dump());
+    }
     Ok(())
 }
 
-struct IParser {
-    in_reader: BufReader<File>,
-    reg_x: i32,
-    cycle: u32,
-    addx_val: Option<i32>,
-    line: String,
+/// An instruction takes a fixed number of cycles, and only changes the registers once its last
+/// cycle has completed
+pub trait Instruction: fmt::Debug {
+    fn cycles(&self) -> u32;
+
+    fn execute(&self, registers: &mut Registers) -> Result<()>;
 }
 
-impl IParser {
-    fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+pub type Program = Vec<Box<dyn Instruction>>;
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
+#[derive(Debug)]
+pub struct Noop;
 
-        Ok(IParser {
-            in_reader,
-            reg_x: 1,
-            cycle: 0,
-            addx_val: None,
-            line,
-        })
+impl Instruction for Noop {
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, _: &mut Registers) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AddX(pub i64);
+
+impl Instruction for AddX {
+    fn cycles(&self) -> u32 {
+        2
+    }
+
+    fn execute(&self, registers: &mut Registers) -> Result<()> {
+        let x = registers.get("x")?;
+        registers.set("x", x + self.0)
+    }
+}
+
+/// decodes the arguments following the mnemonic
+pub type DecodeFn = fn(&[&str]) -> Result<Box<dyn Instruction>>;
+
+/// Turns lines like `addx -3` into instructions by looking up their mnemonic
+pub struct Decoder {
+    instructions: HashMap<String, DecodeFn>,
+}
+
+impl Default for Decoder {
+    /// the instruction set of the puzzle: noop and addx
+    fn default() -> Self {
+        Decoder::empty()
+            .with("noop", |args| match args {
+                [] => Ok(Box::new(Noop)),
+                _ => Err(anyhow!("noop takes no arguments")),
+            })
+            .with("addx", |args| match args {
+                [num] => Ok(Box::new(AddX(num.parse()?))),
+                _ => Err(anyhow!("addx takes exactly one argument")),
+            })
     }
 }
 
-impl Iterator for IParser {
-    type Item = (u32, i32); // (cycle, value of register X)
+impl Decoder {
+    pub fn empty() -> Self {
+        Decoder {
+            instructions: HashMap::new(),
+        }
+    }
+
+    /// adds an instruction to the set, replacing any instruction with the same mnemonic
+    pub fn with(mut self, mnemonic: &str, decode: DecodeFn) -> Self {
+        self.instructions.insert(mnemonic.to_string(), decode);
+        self
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Box<dyn Instruction>> {
+        let mut words = text.split_whitespace();
+        let mnemonic = words.next().ok_or_else(|| anyhow!("empty instruction"))?;
+        let decode = self
+            .instructions
+            .get(mnemonic)
+            .ok_or_else(|| anyhow!("unknown instruction {:?}", mnemonic))?;
+        decode(&words.collect::<Vec<_>>())
+    }
+
+    /// decodes the lines of the input up to the first blank line
+    pub fn decode_input(&self, input: String) -> Result<Program> {
+        input::read_lines(input)?
+            .section()?
+            .iter()
+            .map(|line| {
+                self.decode(&line.text)
+                    .with_context(|| format!("failed to decode {}", line))
+            })
+            .collect()
+    }
+}
+
+/// Named registers - reading or writing a register that was never declared is an error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registers(BTreeMap<String, i64>);
+
+impl Registers {
+    pub fn get(&self, name: &str) -> Result<i64> {
+        self.0
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("unknown register {:?}", name))
+    }
+
+    pub fn set(&mut self, name: &str, value: i64) -> Result<()> {
+        let register = self
+            .0
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("unknown register {:?}", name))?;
+        *register = value;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let registers: Vec<_> = self
+            .0
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{}", registers.join(" "))
+    }
+}
+
+/// called during every cycle with the cycle number (starting at 1) and the registers
+pub type Hook<'a> = Box<dyn FnMut(u64, &Registers) -> Result<()> + 'a>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Breakpoint(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub cycle: u64,
+    pub pc: usize,
+    pub instruction: String,
+    pub registers: Registers,
+}
+
+/// Executes a program cycle by cycle. The cpu pauses during a cycle - after the hooks have seen
+/// it and before the current instruction completes - so the registers show their value during
+/// that cycle.
+pub struct Cpu<'a> {
+    program: Program,
+    registers: Registers,
+    pc: usize,
+    cycle: u64,
+    /// cycles already spent on the current instruction, including a cycle that's in progress
+    elapsed: u32,
+    in_cycle: bool,
+    hooks: Vec<Hook<'a>>,
+    breakpoints: BTreeSet<u64>,
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl<'a> Cpu<'a> {
+    /// the cpu of the puzzle, with a single register x starting at 1
+    pub fn new(program: Program) -> Self {
+        Cpu {
+            program,
+            registers: Registers(BTreeMap::from([("x".to_string(), 1)])),
+            pc: 0,
+            cycle: 0,
+            elapsed: 0,
+            in_cycle: false,
+            hooks: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            trace: None,
+        }
+    }
+
+    /// declares another register, or resets the initial value of an existing one
+    pub fn with_register(mut self, name: &str, value: i64) -> Self {
+        self.registers.0.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn on_cycle(&mut self, hook: impl FnMut(u64, &Registers) -> Result<()> + 'a) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// makes `run` pause during the given cycle
+    pub fn add_breakpoint(&mut self, cycle: u64) {
+        self.breakpoints.insert(cycle);
+    }
+
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// the current cycle, or the last one if the cpu has halted
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// advances into the next cycle - false if the program has ended
+    pub fn step(&mut self) -> Result<bool> {
+        self.finish_cycle()?;
+        // instructions that take no cycles don't show up in hooks or the trace
+        while self.program.get(self.pc).is_some_and(|i| i.cycles() == 0) {
+            self.program[self.pc].execute(&mut self.registers)?;
+            self.pc += 1;
+        }
+        let Some(instruction) = self.program.get(self.pc) else {
+            return Ok(false);
+        };
 
-    fn next(&mut self) -> Option<Self::Item> {
         self.cycle += 1;
+        self.elapsed += 1;
+        self.in_cycle = true;
+        for hook in self.hooks.iter_mut() {
+            hook(self.cycle, &self.registers)
+                .with_context(|| format!("hook failed in cycle {}", self.cycle))?;
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry {
+                cycle: self.cycle,
+                pc: self.pc,
+                instruction: format!("{:?}", instruction),
+                registers: self.registers.clone(),
+            });
+        }
+        Ok(true)
+    }
 
-        let re_noop = Regex::new(r"^noop").unwrap();
-        let re_addx = Regex::new(r"^addx (?P<num>-?\d+)").unwrap();
-
-        if let Some(addx_val) = self.addx_val {
-            let old_reg_x = self.reg_x;
-            self.reg_x += addx_val;
-            self.addx_val = None;
-            Some((self.cycle, old_reg_x))
-        } else {
-            let bytes_read = self
-                .in_reader
-                .read_line(&mut self.line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 || self.line == "\n" {
-                return None; // EOF
+    /// runs until the program ends or a breakpoint is hit
+    pub fn run(&mut self) -> Result<Stop> {
+        while self.step()? {
+            if self.breakpoints.contains(&self.cycle) {
+                return Ok(Stop::Breakpoint(self.cycle));
             }
+        }
+        Ok(Stop::Halted)
+    }
 
-            if re_addx.is_match(&self.line) {
-                let addx_captures = re_addx
-                    .captures(&self.line)
-                    .expect("addx regex failed to capture line");
-                let addx_val_str = &addx_captures
-                    .name("num")
-                    .expect("addx regex didn't contain expected named capture group")
-                    .as_str();
-                let addx_val = addx_val_str
-                    .parse::<i32>()
-                    .expect("failed to parse i32 number from captured string");
-
-                self.addx_val = Some(addx_val);
-
-                self.line.clear();
-
-                Some((self.cycle, self.reg_x))
-            } else if re_noop.is_match(&self.line) {
-                // 1 cycle passes
-                self.line.clear();
-                Some((self.cycle, self.reg_x))
-            } else {
-                panic!("line didn't match regex: {}", self.line);
-            }
+    fn finish_cycle(&mut self) -> Result<()> {
+        if !self.in_cycle {
+            return Ok(());
+        }
+        self.in_cycle = false;
+        let instruction = &self.program[self.pc];
+        if self.elapsed >= instruction.cycles() {
+            instruction
+                .execute(&mut self.registers)
+                .with_context(|| format!("failed to execute {:?}", instruction))?;
+            self.pc += 1;
+            self.elapsed = 0;
+        }
+        Ok(())
+    }
+
+    /// one line per traced cycle: cycle, program counter, instruction and registers
+    pub fn trace_dump(&self) -> String {
+        self.trace
+            .iter()
+            .flatten()
+            .map(|entry| {
+                format!(
+                    "{:>6} {:>4} {:<16} {}",
+                    entry.cycle, entry.pc, entry.instruction, entry.registers
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::task_solver::day_10::{Cpu, Decoder, Instruction, Registers, Stop};
+
+    /// multiplies a register by a factor, which takes 3 cycles
+    #[derive(Debug)]
+    struct Mul(String, i64);
+
+    impl Instruction for Mul {
+        fn cycles(&self) -> u32 {
+            3
+        }
+
+        fn execute(&self, registers: &mut Registers) -> anyhow::Result<()> {
+            let value = registers.get(&self.0)?;
+            registers.set(&self.0, value * self.1)
         }
     }
+
+    #[test]
+    fn custom_instructions_and_breakpoints() {
+        let decoder = Decoder::default().with("mul", |args| match args {
+            [register, factor] => Ok(Box::new(Mul(register.to_string(), factor.parse()?))),
+            _ => Err(anyhow!("mul takes a register and a factor")),
+        });
+        assert!(decoder.decode("jmp 3").is_err());
+        let program = ["addx 4", "mul y 3", "noop"]
+            .into_iter()
+            .map(|text| decoder.decode(text))
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+
+        let mut cpu = Cpu::new(program).with_register("y", 2);
+        cpu.add_breakpoint(3);
+        cpu.enable_trace();
+        assert_eq!(cpu.run().unwrap(), Stop::Breakpoint(3));
+        assert_eq!((cpu.pc(), cpu.registers().get("x").unwrap()), (1, 5));
+        assert_eq!(cpu.registers().get("y").unwrap(), 2);
+
+        assert_eq!(cpu.run().unwrap(), Stop::Halted);
+        assert_eq!(cpu.cycle(), 6);
+        assert_eq!(cpu.registers().to_string(), "x=5 y=6");
+        assert_eq!(cpu.trace_dump().lines().count(), 6);
+    }
+
+    #[test]
+    fn unknown_register() {
+        let program = vec![Box::new(Mul("z".to_string(), 2)) as Box<dyn Instruction>];
+        assert!(Cpu::new(program).run().is_err());
+    }
 }
@@ -1,213 +1,606 @@
-use anyhow::{anyhow, Context, Ok, Result};
+use anyhow::{anyhow, Context, Result};
 
 use log::{debug, info};
-use regex::Regex;
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use num::{integer, BigUint, Zero};
+use std::{collections::HashMap, fmt, iter::Peekable, str::FromStr};
 
-use super::util::{self, SortedList};
+use super::util::{capture, input, top_k::TopK};
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let mut parser = MonkeyParser::init(input, task).context("failed to instantiate parser")?;
+const RE_MONKEY: &str = concat!(
+    r"Monkey (?P<monkey_no>\d+):\n",
+    r"\s*Starting items: (?P<start_list>(?:\d+(?:, )?)*)\n",
+    r"\s*Operation: new = (?P<operation>[^\n]+)\n",
+    r"\s*Test: divisible by (?P<test>\d+)\n",
+    r"\s*If true: throw to monkey (?P<if_true>\d+)\n",
+    r"\s*If false: throw to monkey (?P<if_false>\d+)"
+);
 
-    debug!("parsed {} monkeys", parser.monkey_map.len(),);
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let mode = match task {
+        1 => WorryMode::Exact,
+        2 => WorryMode::ModuloLcm,
+        _ => return Err(anyhow!("task doesn't exist!")),
+    };
+    solve_with(task, input, mode)
+}
 
-    let n_rounds = match task {
-        1 => 20,
-        2 => 10000,
+/// plays the rounds of the task with worry levels represented according to the mode
+pub fn solve_with(task: u8, input: String, mode: WorryMode) -> Result<String> {
+    let (n_rounds, relief) = match task {
+        1 => (20, true),
+        2 => (10000, false),
         _ => return Err(anyhow!("task doesn't exist!")),
     };
+    let mut parser =
+        MonkeyParser::init(input, mode, relief).context("failed to instantiate parser")?;
 
-    let monkey_business = parser
-        .nth(n_rounds - 1)
-        .expect("failed to iterate over 20 rounds")
-        .fold(1u64, &|x, y| *x as u64 * y);
+    debug!("parsed {} monkeys", parser.monkey_map.len(),);
+    debug!("\n{}", dump(&parser.monkeys()));
+
+    let mut inspections = TopK::new(2);
+    for _ in 0..n_rounds {
+        inspections = parser.next().expect("monkeys keep playing forever")?;
+    }
+    let monkey_business = inspections
+        .iter()
+        .try_fold(1u64, |product, &n| product.checked_mul(n))
+        .ok_or_else(|| anyhow!("level of monkey business overflows"))?;
 
     info!(
         "level of monkey business after {} rounds: {}",
         n_rounds, monkey_business
     );
 
-    Ok(())
+    Ok(monkey_business.to_string())
 }
 
-struct Monkey {
-    item_list: Vec<u32>,
-    operation: Box<dyn Fn(u32) -> u32>,
-    test: u32,
-    if_true: u32,
-    if_false: u32,
-    no_inspections: u32,
+/// Arithmetic on the worry level of an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Const(u64),
+    Binary(Box<Expr>, Op, Box<Expr>),
 }
 
-struct MonkeyParser {
-    monkey_map: HashMap<u32, Monkey>,
-    worry_congruences: HashMap<u32, HashMap<u32, u32>>, // { item_id -> { mod_val -> curr_val } }
-    is_task_1: bool,
-    round: u32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Pow,
 }
 
-fn apply_operation(
-    worry_congruences: &mut HashMap<u32, HashMap<u32, u32>>,
-    item_id: u32,
-    operation: &dyn Fn(u32) -> u32,
-    is_task_1: bool,
-) {
-    let item_congruences = worry_congruences.get_mut(&item_id).unwrap();
-    for (m, val) in item_congruences.iter_mut() {
-        let mut op_val = operation(*val);
-        if is_task_1 {
-            debug!("finding mod inv of {} for {}", 3, *m as i32);
-            let mod_inv = modinverse::modinverse(3, *m as i32).unwrap();
-            debug!("mod inv: {}", mod_inv);
-            op_val = op_val * mod_inv as u32;
+impl Op {
+    fn symbol(&self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Pow => '^',
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul => 2,
+            Op::Pow => 3,
         }
-        *val = op_val % m;
     }
 }
 
-impl MonkeyParser {
-    fn init(input: String, task: u8) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-
-        let re_monkey = Regex::new(r"Monkey (?P<monkey_no>\d+):").unwrap();
-        let re_starting_items =
-            Regex::new(r"Starting items: (?P<start_list>(?:\d+(?:, )?)+)").unwrap();
-        let re_operation =
-            Regex::new(r"Operation: new = old (?P<op>[+*]) (?P<val>old|\d+)").unwrap();
-        let re_test = Regex::new(r"Test: divisible by (?P<val>\d+)").unwrap();
-        let re_if_true = Regex::new(r"If true: throw to monkey (?P<monkey_no>\d+)").unwrap();
-        let re_if_false = Regex::new(r"If false: throw to monkey (?P<monkey_no>\d+)").unwrap();
+impl Expr {
+    fn binary(lhs: Expr, op: Op, rhs: Expr) -> Self {
+        Expr::Binary(Box::new(lhs), op, Box::new(rhs))
+    }
 
-        let mut monkey_map = HashMap::new();
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, op, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+
+    pub fn uses_old(&self) -> bool {
+        match self {
+            Expr::Old => true,
+            Expr::Const(_) => false,
+            Expr::Binary(lhs, _, rhs) => lhs.uses_old() || rhs.uses_old(),
+        }
+    }
+
+    pub fn uses_op(&self, op: Op) -> bool {
+        match self {
+            Expr::Binary(lhs, binary_op, rhs) => {
+                *binary_op == op || lhs.uses_op(op) || rhs.uses_op(op)
+            }
+            _ => false,
+        }
+    }
 
-        // can construct worry_congruences map after parsing monkeys
-        let mut item_count = 0u32;
-        let mut item_vals = HashMap::new(); // { item_id -> initial worry_level }
-        let mut mod_vals = Vec::new(); // [ test values ]
-
-        loop {
-            while in_reader
-                .read_line(&mut line)
-                .expect("failed to read line in input file")
-                != 0
-                && !(re_monkey.is_match(&line)
-                    && re_starting_items.is_match(&line)
-                    && re_operation.is_match(&line)
-                    && re_test.is_match(&line)
-                    && re_if_true.is_match(&line)
-                    && re_if_false.is_match(&line))
-            {}
-
-            if line.trim().is_empty() {
-                return Ok(MonkeyParser {
-                    monkey_map,
-                    worry_congruences: item_vals
-                        .drain()
-                        .map(|(item_id, init_val): (u32, u32)| {
-                            (
-                                item_id,
-                                mod_vals.iter().map(|m: &u32| (*m, init_val % m)).collect(),
-                            )
-                        })
-                        .collect(),
-                    is_task_1: task == 1,
-                    round: 0u32,
-                });
-            } else if !(re_monkey.is_match(&line)
-                && re_starting_items.is_match(&line)
-                && re_operation.is_match(&line)
-                && re_test.is_match(&line)
-                && re_if_true.is_match(&line)
-                && re_if_false.is_match(&line))
-            {
-                return Err(anyhow!("missing monkey data!"));
-            } else {
-                let monkey_no =
-                    util::capture_and_parse(&re_monkey, &line, "monkey_no", &|s: &str| {
-                        s.parse::<u32>().expect("failed to parse monkey number")
-                    });
-                let mut item_list =
-                    util::capture_and_parse(&re_starting_items, &line, "start_list", &|s: &str| {
-                        s.split(',')
-                            .map(|n| {
-                                n.trim()
-                                    .parse::<u32>()
-                                    .expect("failed to parse worry level")
-                            })
-                            .collect::<Vec<u32>>()
-                    });
-                for item_val in item_list.iter_mut() {
-                    item_vals.insert(item_count, *item_val);
-                    *item_val = item_count;
-                    item_count += 1;
+    /// exponents must not depend on the worry level, so that the expression can be evaluated
+    /// modulo some number
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Expr::Binary(_, Op::Pow, rhs) if rhs.uses_old() => {
+                Err(anyhow!("exponent of {} depends on old", self))
+            }
+            Expr::Binary(lhs, _, rhs) => lhs.validate().and(rhs.validate()),
+            _ => Ok(()),
+        }
+    }
+
+    /// the new worry level - errors if it overflows or drops below 0
+    pub fn eval(&self, old: u64) -> Result<u64> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Const(c) => Ok(*c),
+            Expr::Binary(lhs, op, rhs) => {
+                let (a, b) = (lhs.eval(old)?, rhs.eval(old)?);
+                match op {
+                    Op::Add => a.checked_add(b),
+                    Op::Sub => a.checked_sub(b),
+                    Op::Mul => a.checked_mul(b),
+                    Op::Pow => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
                 }
-                let test = util::capture_and_parse(&re_test, &line, "val", &|s: &str| {
-                    s.parse::<u32>().expect("failed to parse test number")
-                });
-                mod_vals.push(test);
-                let if_true =
-                    util::capture_and_parse(&re_if_true, &line, "monkey_no", &|s: &str| {
-                        s.parse::<u32>().expect("failed to parse if_true number")
-                    });
-                let if_false =
-                    util::capture_and_parse(&re_if_false, &line, "monkey_no", &|s: &str| {
-                        s.parse::<u32>().expect("failed to parse if_false number")
-                    });
-                let operation_name =
-                    util::capture_and_parse(&re_operation, &line, "op", &|s| s.to_owned());
-                let operation_val =
-                    util::capture_and_parse(&re_operation, &line, "val", &|s| s.to_owned());
-                let monkey = Monkey {
-                    item_list,
-                    test,
-                    if_true,
-                    if_false,
-                    no_inspections: 0u32,
-                    operation: if operation_name == "+" {
-                        if operation_val == "old" {
-                            Box::new(|n: u32| n + n)
-                        } else {
-                            let val = operation_val
-                                .parse::<u32>()
-                                .expect("couldn't parse number from op val");
-                            Box::new(move |n: u32| n + val)
+                .ok_or_else(|| anyhow!("{} {} {} is out of range", a, op.symbol(), b))
+            }
+        }
+    }
+
+    /// the new worry level for levels that don't fit into a u64
+    pub fn eval_big(&self, old: &BigUint) -> Result<BigUint> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Const(c) => Ok(BigUint::from(*c)),
+            Expr::Binary(lhs, op, rhs) => {
+                let a = lhs.eval_big(old)?;
+                match op {
+                    Op::Add => Ok(a + rhs.eval_big(old)?),
+                    Op::Sub => {
+                        let b = rhs.eval_big(old)?;
+                        if b > a {
+                            return Err(anyhow!("{} - {} is out of range", a, b));
                         }
-                    } else if operation_name == "*" {
-                        if operation_val == "old" {
-                            Box::new(|n: u32| n * n)
-                        } else {
-                            let val = operation_val
-                                .parse::<u32>()
-                                .expect("couldn't parse number from op val");
-                            Box::new(move |n: u32| n * val)
+                        Ok(a - b)
+                    }
+                    Op::Mul => Ok(a * rhs.eval_big(old)?),
+                    Op::Pow => Ok(a.pow(u32::try_from(self.exponent(rhs)?)?)),
+                }
+            }
+        }
+    }
+
+    /// the new worry level modulo m, given the old one modulo m
+    pub fn eval_mod(&self, old: u64, m: u64) -> Result<u64> {
+        match self {
+            Expr::Old | Expr::Const(_) => Ok(self.eval(old)? % m),
+            Expr::Binary(lhs, op, rhs) => {
+                let a = lhs.eval_mod(old, m)? as u128;
+                let b = || rhs.eval_mod(old, m).map(u128::from);
+                let modulus = m as u128;
+                let result = match op {
+                    Op::Add => (a + b()?) % modulus,
+                    Op::Sub => (a + modulus - b()?) % modulus,
+                    Op::Mul => a * b()? % modulus,
+                    Op::Pow => {
+                        // square and multiply, with the exponent itself not taken modulo m
+                        let (mut base, mut exp) = (a, self.exponent(rhs)?);
+                        let mut result = 1 % modulus;
+                        while exp > 0 {
+                            if exp & 1 == 1 {
+                                result = result * base % modulus;
+                            }
+                            base = base * base % modulus;
+                            exp >>= 1;
                         }
+                        result
+                    }
+                };
+                Ok(result as u64)
+            }
+        }
+    }
+
+    /// value of the exponent of this power - only exponents that don't depend on old can be
+    /// evaluated without knowing the exact worry level
+    fn exponent(&self, rhs: &Expr) -> Result<u64> {
+        if rhs.uses_old() {
+            return Err(anyhow!("exponent of {} depends on old", self));
+        }
+        rhs.eval(0)
+    }
+}
+
+impl fmt::Display for Expr {
+    /// only adds the parentheses that are needed to keep the structure of the tree
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(c) => write!(f, "{}", c),
+            Expr::Binary(lhs, op, rhs) => {
+                // ^ is right-associative, everything else left-associative
+                let (lhs_parens, rhs_parens) = match op {
+                    Op::Pow => (
+                        lhs.precedence() <= op.precedence(),
+                        rhs.precedence() < op.precedence(),
+                    ),
+                    _ => (
+                        lhs.precedence() < op.precedence(),
+                        rhs.precedence() <= op.precedence(),
+                    ),
+                };
+                let side = |f: &mut fmt::Formatter<'_>, expr: &Expr, parens: bool| {
+                    if parens {
+                        write!(f, "({})", expr)
                     } else {
-                        return Err(anyhow!("unknown operation {}", operation_name));
-                    },
+                        write!(f, "{}", expr)
+                    }
                 };
-                if let Some(_) = monkey_map.insert(monkey_no, monkey) {
-                    return Err(anyhow!("map contained duplicate monkey!"));
-                } else {
-                    line.clear();
+                side(f, lhs, lhs_parens)?;
+                write!(f, " {} ", op.symbol())?;
+                side(f, rhs, rhs_parens)
+            }
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = anyhow::Error;
+
+    /// `+` and `-` bind weakest, then `*`, then `^` - parentheses group as usual
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(s)?.into_iter().peekable();
+        let expr = parse_sum(&mut tokens)?;
+        match tokens.next() {
+            None => Ok(expr),
+            Some(token) => Err(anyhow!("unexpected {:?} in {:?}", token, s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Old,
+    Num(u64),
+    Op(Op),
+    Open,
+    Close,
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            ' ' => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' => {
+                let mut num = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    num.push(digit);
+                }
+                tokens.push(Token::Num(num.parse()?));
+                continue;
+            }
+            'o' => {
+                let word: String = chars.by_ref().take(3).collect();
+                if word != "old" {
+                    return Err(anyhow!("unexpected {:?} in {:?}", word, s));
                 }
+                tokens.push(Token::Old);
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ => return Err(anyhow!("unexpected {:?} in {:?}", c, s)),
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn parse_sum(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = parse_product(tokens)?;
+    while let Some(Token::Op(op @ (Op::Add | Op::Sub))) = tokens.peek().cloned() {
+        tokens.next();
+        expr = Expr::binary(expr, op, parse_product(tokens)?);
+    }
+    Ok(expr)
+}
+
+fn parse_product(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = parse_power(tokens)?;
+    while tokens.next_if_eq(&Token::Op(Op::Mul)).is_some() {
+        expr = Expr::binary(expr, Op::Mul, parse_power(tokens)?);
+    }
+    Ok(expr)
+}
+
+fn parse_power(tokens: &mut Tokens) -> Result<Expr> {
+    let base = parse_atom(tokens)?;
+    if tokens.next_if_eq(&Token::Op(Op::Pow)).is_some() {
+        return Ok(Expr::binary(base, Op::Pow, parse_power(tokens)?));
+    }
+    Ok(base)
+}
+
+fn parse_atom(tokens: &mut Tokens) -> Result<Expr> {
+    match tokens.next() {
+        Some(Token::Old) => Ok(Expr::Old),
+        Some(Token::Num(n)) => Ok(Expr::Const(n)),
+        Some(Token::Open) => {
+            let expr = parse_sum(tokens)?;
+            match tokens.next() {
+                Some(Token::Close) => Ok(expr),
+                token => Err(anyhow!("expected ')', found {:?}", token)),
             }
         }
+        token => Err(anyhow!("expected an operand, found {:?}", token)),
     }
 }
 
-impl Iterator for MonkeyParser {
-    type Item = SortedList<u32>; // [no of item inspections per monkey in this round]
+pub struct Monkey {
+    number: u32,
+    /// worry levels of the items the monkey starts with
+    starting_items: Vec<u64>,
+    /// ids of the items the monkey currently holds
+    item_list: Vec<usize>,
+    operation: Expr,
+    test: u64,
+    if_true: u32,
+    if_false: u32,
+    no_inspections: u64,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
+impl fmt::Display for Monkey {
+    /// the monkey's paragraph of the input
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.starting_items.iter().map(u64::to_string).collect();
+        writeln!(f, "Monkey {}:", self.number)?;
+        writeln!(f, "  Starting items: {}", items.join(", "))?;
+        writeln!(f, "  Operation: new = {}", self.operation)?;
+        writeln!(f, "  Test: divisible by {}", self.test)?;
+        writeln!(f, "    If true: throw to monkey {}", self.if_true)?;
+        write!(f, "    If false: throw to monkey {}", self.if_false)
+    }
+}
+
+/// the monkeys in the input format
+pub fn dump(monkeys: &[&Monkey]) -> String {
+    monkeys
+        .iter()
+        .map(|monkey| monkey.to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// How worry levels are represented - only the exact mode can divide them by 3 after inspections,
+/// the others only keep enough information to decide the divisibility tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorryMode {
+    /// checked u64 arithmetic that switches to big integers once a level doesn't fit anymore
+    Exact,
+    /// levels modulo the product of the test divisors
+    ModuloProduct,
+    /// levels modulo the least common multiple of the test divisors
+    ModuloLcm,
+    /// levels modulo each test divisor
+    Congruences,
+}
+
+#[derive(Debug, Clone)]
+enum Worry {
+    Small(u64),
+    Big(BigUint),
+    Modulo(u64),
+    /// one residue per divisor of the arithmetic
+    Congruences(Vec<u64>),
+}
+
+struct Arithmetic {
+    mode: WorryMode,
+    /// whether worry levels are divided by 3 after each inspection
+    relief: bool,
+    modulus: u64,
+    /// distinct test divisors in ascending order
+    divisors: Vec<u64>,
+}
+
+impl Arithmetic {
+    fn new(mode: WorryMode, relief: bool, monkeys: &[&Monkey]) -> Result<Self> {
+        if mode != WorryMode::Exact {
+            if relief {
+                return Err(anyhow!(
+                    "{:?} can't represent worry levels that are divided by 3",
+                    mode
+                ));
+            }
+            // residues can't tell whether a level drops below 0
+            if let Some(monkey) = monkeys.iter().find(|m| m.operation.uses_op(Op::Sub)) {
+                return Err(anyhow!(
+                    "{:?} can't represent worry levels that are decreased - monkey {} computes {}",
+                    mode,
+                    monkey.number,
+                    monkey.operation
+                ));
+            }
+        }
+        let tests: Vec<u64> = monkeys.iter().map(|monkey| monkey.test).collect();
+        let modulus = match mode {
+            WorryMode::ModuloProduct => tests.iter().try_fold(1u64, |m, &d| m.checked_mul(d)),
+            WorryMode::ModuloLcm => tests
+                .iter()
+                .try_fold(1u64, |m, &d| (m / integer::gcd(m, d)).checked_mul(d)),
+            _ => Some(0),
+        }
+        .ok_or_else(|| anyhow!("the {:?} modulus of {:?} overflows", mode, tests))?;
+
+        let mut divisors = tests;
+        divisors.sort_unstable();
+        divisors.dedup();
+        Ok(Arithmetic {
+            mode,
+            relief,
+            modulus,
+            divisors,
+        })
+    }
+
+    fn worry(&self, level: u64) -> Worry {
+        match self.mode {
+            WorryMode::Exact => Worry::Small(level),
+            WorryMode::ModuloProduct | WorryMode::ModuloLcm => Worry::Modulo(level % self.modulus),
+            WorryMode::Congruences => {
+                Worry::Congruences(self.divisors.iter().map(|d| level % d).collect())
+            }
+        }
+    }
+
+    fn inspect(&self, worry: &mut Worry, operation: &Expr) -> Result<()> {
+        match worry {
+            Worry::Small(level) => match operation.eval(*level) {
+                Ok(new) => *level = if self.relief { new / 3 } else { new },
+                Err(_) => {
+                    // too large for a u64 - if it's negative, the big integers report it
+                    *worry = Worry::Big(BigUint::from(*level));
+                    return self.inspect(worry, operation);
+                }
+            },
+            Worry::Big(level) => {
+                let new = operation.eval_big(level)?;
+                *level = if self.relief { new / 3u32 } else { new };
+            }
+            Worry::Modulo(level) => *level = operation.eval_mod(*level, self.modulus)?,
+            Worry::Congruences(residues) => {
+                for (residue, &d) in residues.iter_mut().zip(&self.divisors) {
+                    *residue = operation.eval_mod(*residue, d)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_divisible(&self, worry: &Worry, test: u64) -> bool {
+        match worry {
+            Worry::Small(level) => level % test == 0,
+            Worry::Big(level) => (level % test).is_zero(),
+            // the modulus is a multiple of the test divisor
+            Worry::Modulo(level) => level % test == 0,
+            Worry::Congruences(residues) => {
+                let i = self
+                    .divisors
+                    .binary_search(&test)
+                    .expect("test divisors are part of the arithmetic");
+                residues[i] == 0
+            }
+        }
+    }
+}
+
+struct MonkeyParser {
+    monkey_map: HashMap<u32, Monkey>,
+    worry_levels: Vec<Worry>, // [ worry level of item_id ]
+    arithmetic: Arithmetic,
+    round: u32,
+}
+
+impl MonkeyParser {
+    fn init(input: String, mode: WorryMode, relief: bool) -> Result<Self> {
+        let mut monkey_map = HashMap::new();
+        let mut item_vals = Vec::new(); // [ initial worry level of item_id ]
+
+        for paragraph in input::read_lines(input)?.paragraphs() {
+            let paragraph = paragraph?;
+            let line = paragraph
+                .iter()
+                .map(|l| l.text.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n");
+
+            let captures = capture::captures_from(
+                RE_MONKEY,
+                &line,
+                format!("monkey paragraph starting at {}", paragraph[0]),
+            )?;
+
+            let monkey_no: u32 = captures.get("monkey_no")?;
+            let starting_items = captures
+                .as_str("start_list")
+                .unwrap_or_default()
+                .split(',')
+                .filter(|n| !n.trim().is_empty())
+                .map(|n| {
+                    n.trim().parse::<u64>().context(format!(
+                        "failed to parse worry level of monkey {}",
+                        monkey_no
+                    ))
+                })
+                .collect::<Result<Vec<u64>>>()?;
+            let item_list = (item_vals.len()..item_vals.len() + starting_items.len()).collect();
+            item_vals.extend(&starting_items);
+
+            let (test, if_true, if_false) = captures.extract(["test", "if_true", "if_false"])?;
+            if test == 0 {
+                return Err(anyhow!("monkey {} tests divisibility by 0", monkey_no));
+            }
+            let operation: Expr = captures.get("operation")?;
+            operation
+                .validate()
+                .with_context(|| format!("invalid operation of monkey {}", monkey_no))?;
+            let monkey = Monkey {
+                number: monkey_no,
+                starting_items,
+                item_list,
+                test,
+                if_true,
+                if_false,
+                no_inspections: 0,
+                operation,
+            };
+            if monkey_map.insert(monkey_no, monkey).is_some() {
+                return Err(anyhow!("map contained duplicate monkey!"));
+            }
+        }
+        for monkey in monkey_map.values() {
+            for target in [monkey.if_true, monkey.if_false] {
+                if !monkey_map.contains_key(&target) || target == monkey.number {
+                    return Err(anyhow!(
+                        "monkey {} can't throw to monkey {}",
+                        monkey.number,
+                        target
+                    ));
+                }
+            }
+        }
+
+        let monkeys: Vec<&Monkey> = monkey_map.values().collect();
+        let arithmetic = Arithmetic::new(mode, relief, &monkeys)?;
+        Ok(MonkeyParser {
+            monkey_map,
+            worry_levels: item_vals.iter().map(|&v| arithmetic.worry(v)).collect(),
+            arithmetic,
+            round: 0u32,
+        })
+    }
+
+    /// the monkeys in order of their number
+    fn monkeys(&self) -> Vec<&Monkey> {
+        let mut monkeys: Vec<&Monkey> = self.monkey_map.values().collect();
+        monkeys.sort_unstable_by_key(|monkey| monkey.number);
+        monkeys
+    }
+
+    fn play_round(&mut self) -> Result<TopK<u64>> {
         self.round += 1;
         debug!("Running round {}", self.round);
 
@@ -215,60 +608,169 @@ impl Iterator for MonkeyParser {
         let mut monkey_order = self.monkey_map.keys().cloned().collect::<Vec<u32>>();
         monkey_order.sort_unstable();
 
-        // cannot hold multiple mutable references to monkey_map --> tmp store for moved items
-        let mut passed_items: HashMap<u32, Vec<u32>> = HashMap::new();
-
-        let mut inspection_list = util::SortedList::new(2);
+        let mut inspection_list = TopK::new(2);
 
         for monkey_no in monkey_order.iter() {
             let active_monkey = self
                 .monkey_map
                 .get_mut(monkey_no)
                 .expect("monkey doesn't exist!");
-            let item_list = &mut active_monkey.item_list;
-            let mut passed_item_list = passed_items.remove(monkey_no).unwrap_or(vec![]);
-            let item_list_full = item_list.drain(..).chain(passed_item_list.drain(..));
-
-            for item_id in item_list_full {
-                active_monkey.no_inspections += 1;
-                apply_operation(
-                    &mut self.worry_congruences,
-                    item_id,
-                    &active_monkey.operation,
-                    self.is_task_1,
-                );
-                let new_owner;
-                if *self
-                    .worry_congruences
-                    .get(&item_id)
-                    .unwrap()
-                    .get(&active_monkey.test)
-                    .unwrap()
-                    == 0u32
-                {
-                    new_owner = active_monkey.if_true;
+            // the items are only handed on once all of them have been inspected
+            let mut thrown = Vec::with_capacity(active_monkey.item_list.len());
+            for &item_id in &active_monkey.item_list {
+                let worry = &mut self.worry_levels[item_id];
+                self.arithmetic
+                    .inspect(worry, &active_monkey.operation)
+                    .with_context(|| {
+                        format!(
+                            "monkey {} failed to inspect item {} in round {}",
+                            monkey_no, item_id, self.round
+                        )
+                    })?;
+                let new_owner = if self.arithmetic.is_divisible(worry, active_monkey.test) {
+                    active_monkey.if_true
                 } else {
-                    new_owner = active_monkey.if_false;
-                }
+                    active_monkey.if_false
+                };
+                thrown.push((new_owner, item_id));
+            }
+            active_monkey.no_inspections += thrown.len() as u64;
+            active_monkey.item_list.clear();
+            inspection_list.insert(active_monkey.no_inspections);
 
-                if passed_items.contains_key(&new_owner) {
-                    passed_items.get_mut(&new_owner).unwrap().push(item_id);
-                } else {
-                    passed_items.insert(new_owner, vec![item_id]);
-                }
+            for (new_owner, item_id) in thrown {
+                self.monkey_map
+                    .get_mut(&new_owner)
+                    .expect("targets are checked while parsing")
+                    .item_list
+                    .push(item_id);
             }
+        }
 
-            inspection_list.insert(active_monkey.no_inspections);
+        Ok(inspection_list)
+    }
+}
+
+impl Iterator for MonkeyParser {
+    type Item = Result<TopK<u64>>; // [most item inspections of any monkey so far]
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.play_round())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::task_solver::day_11::{self, dump, Expr, MonkeyParser, Worry, WorryMode};
+
+    fn sample() -> String {
+        format!("{}/input/samples/task_11.input", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// the sample with the operation of monkey 0 replaced - the input lives as long as the dir
+    fn sample_with(operation: &str) -> (TempDir, String) {
+        let text = fs::read_to_string(sample())
+            .unwrap()
+            .replacen("old * 19", operation, 1);
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("task_11.input");
+        fs::write(&input, text).unwrap();
+        (dir, input.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn expressions() {
+        for (text, printed, old, new) in [
+            ("old * 19", "old * 19", 2, 38),
+            ("old*old", "old * old", 3, 9),
+            ("(old + 3) * 2 - 1", "(old + 3) * 2 - 1", 4, 13),
+            ("old - (3 - 1)", "old - (3 - 1)", 4, 2),
+            ("2 ^ 3 ^ 2", "2 ^ 3 ^ 2", 0, 512),
+            ("(old ^ 2) ^ 3", "(old ^ 2) ^ 3", 2, 64),
+        ] {
+            let expr: Expr = text.parse().unwrap();
+            assert_eq!(expr.to_string(), printed);
+            assert_eq!(printed.parse::<Expr>().unwrap(), expr);
+            assert_eq!(expr.eval(old).unwrap(), new);
+            assert_eq!(expr.eval_mod(old, 7).unwrap(), new % 7);
+        }
+        assert!("old - 5".parse::<Expr>().unwrap().eval(4).is_err());
+        let old_exponent: Expr = "2 ^ old".parse().unwrap();
+        assert!(old_exponent.validate().is_err());
+        assert!(old_exponent.eval_mod(3, 7).is_err());
+        assert_eq!(old_exponent.eval(3).unwrap(), 8);
+        for invalid in ["old +", "(old", "old old", "olf * 2", "old / 2"] {
+            assert!(invalid.parse::<Expr>().is_err(), "{}", invalid);
         }
+    }
 
-        for (monkey_no, item_list) in passed_items.iter_mut() {
-            self.monkey_map
-                .get_mut(monkey_no)
-                .unwrap()
-                .item_list
-                .append(item_list);
+    #[test]
+    fn dump_round_trip() {
+        let input = sample();
+        let parser = MonkeyParser::init(input.clone(), WorryMode::Exact, true).unwrap();
+        assert_eq!(
+            dump(&parser.monkeys()),
+            fs::read_to_string(input).unwrap().trim_end()
+        );
+    }
+
+    #[test]
+    fn worry_modes() {
+        let input = sample();
+        for mode in [
+            WorryMode::ModuloProduct,
+            WorryMode::ModuloLcm,
+            WorryMode::Congruences,
+        ] {
+            let answer = day_11::solve_with(2, input.clone(), mode).unwrap();
+            assert_eq!(answer, "2713310158", "{:?}", mode);
+            assert!(day_11::solve_with(1, input.clone(), mode).is_err());
         }
+    }
+
+    #[test]
+    fn failing_inspections() {
+        // the first item of monkey 0 drops below 0 in the very first round
+        let (_dir, input) = sample_with("old - 1000");
+        assert!(day_11::solve(1, input.clone()).is_err());
+        let mut parser = MonkeyParser::init(input.clone(), WorryMode::Exact, true).unwrap();
+        assert!(parser.next().unwrap().is_err());
+        let monkey = &parser.monkey_map[&0];
+        assert_eq!(monkey.item_list.len(), 2);
+        assert_eq!(monkey.no_inspections, 0);
+
+        // only exact worry levels can become negative
+        for mode in [
+            WorryMode::ModuloProduct,
+            WorryMode::ModuloLcm,
+            WorryMode::Congruences,
+        ] {
+            assert!(
+                day_11::solve_with(2, input.clone(), mode).is_err(),
+                "{:?}",
+                mode
+            );
+        }
+        let (_dir, input) = sample_with("old * 19 - 1");
+        assert!(day_11::solve(2, input).is_err());
+    }
 
-        Some(inspection_list)
+    #[test]
+    fn exact_mode_switches_to_big_integers() {
+        let mut parser = MonkeyParser::init(sample(), WorryMode::Exact, false).unwrap();
+        // the monkey squaring its items makes levels outgrow a u64 within a few dozen rounds
+        let exact = parser.nth(49).unwrap().unwrap();
+        let big = |worry: &Worry| matches!(worry, Worry::Big(_));
+        assert!(parser.worry_levels.iter().any(big));
+        let mut expected = MonkeyParser::init(sample(), WorryMode::Congruences, false).unwrap();
+        let expected = expected.nth(49).unwrap().unwrap();
+        assert_eq!(
+            exact.iter().collect::<Vec<_>>(),
+            expected.iter().collect::<Vec<_>>()
+        );
     }
 }
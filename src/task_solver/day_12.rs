@@ -1,176 +1,79 @@
-use anyhow::{anyhow, Context, Ok, Result};
+use anyhow::{anyhow, bail, Context, Ok, Result};
 use log::{debug, info};
-use petgraph::{algo::dijkstra, graph::NodeIndex, Directed, Graph};
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let heightmap = parse_heightmap(input).context("Failed to instantiate heightmap")?;
-
-    let (path_graph, s, e) =
-        grid_to_graph(heightmap).context("failed to convert the parsed heightmap to graph")?;
+use super::util::{input, search};
 
-    debug!("graph looks like: {:#?}", path_graph);
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let heightmap = parse_heightmap(input).context("Failed to instantiate heightmap")?;
 
-    let sp_map = dijkstra(&path_graph, e, None, |_| 1);
+    let s = find_position(&heightmap, 'S').context("heightmap doesn't contain node S")?;
+    let e = find_position(&heightmap, 'E').context("heightmap doesn't contain node E")?;
 
-    debug!("sp map: {:#?}", sp_map);
+    // search backwards from E, i.e. we may step down by at most one unit
+    let reverse_moves = |&(i, j): &Position| {
+        let elevation = char_to_int(heightmap[i][j]);
+        let mut moves = vec![(i + 1, j), (i, j + 1)];
+        if i > 0 {
+            moves.push((i - 1, j));
+        }
+        if j > 0 {
+            moves.push((i, j - 1));
+        }
+        moves.retain(|&(d_i, d_j)| {
+            d_i < heightmap.len()
+                && d_j < heightmap[d_i].len()
+                && char_to_int(heightmap[d_i][d_j]) >= elevation - 1
+        });
+        moves.into_iter().map(|d| (d, 1u32))
+    };
+
+    let shortest_path = match task {
+        1 => search::astar(
+            [e],
+            reverse_moves,
+            |p| *p,
+            |&(i, j)| (i.abs_diff(s.0) + j.abs_diff(s.1)) as u32,
+            |p| *p == s,
+        ),
+        2 => search::dijkstra(
+            [e],
+            reverse_moves,
+            |p| *p,
+            |&(i, j)| char_to_int(heightmap[i][j]) == 0,
+        ),
+        _ => bail!("task doesn't exist!"),
+    }
+    .ok_or(anyhow!("heightmap doesn't contain any paths to node E"))?;
 
-    let shortest_dist = sp_map
-        .iter()
-        .filter(|(node_id, _)| match task {
-            1 => **node_id == s,
-            2 => path_graph[**node_id] == 'a',
-            _ => panic!("task doesn't exist!"),
-        })
-        .map(|(_, len)| len)
-        .min()
-        .ok_or(anyhow!(
-            "shortest path map didn't contain any paths to node E: {:?}",
-            sp_map
-        ))?;
+    debug!("shortest path: {:?}", shortest_path.nodes);
 
-    info!("shortest path to E has length {}", shortest_dist);
+    info!("shortest path to E has length {}", shortest_path.cost);
 
-    Ok(())
+    Ok(shortest_path.cost.to_string())
 }
 
 type HeightMap = Vec<Vec<char>>;
-type Paths = Graph<char, (), Directed>;
+type Position = (usize, usize);
 
 fn parse_heightmap(input: String) -> Result<HeightMap> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
-    let mut heightmap = HeightMap::new();
-
-    loop {
-        let bytes_read = in_reader
-            .read_line(&mut line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 || line == "\n" {
-            break; // EOF
-        }
-
-        heightmap.push(line.trim().chars().collect());
-
-        line.clear();
-    }
-
-    Ok(heightmap)
+    Ok(input::read_lines(input)?
+        .section()?
+        .into_iter()
+        .map(|line| line.text.trim().chars().collect())
+        .collect())
 }
 
-fn grid_to_graph(heightmap: HeightMap) -> Result<(Paths, NodeIndex, NodeIndex)> {
-    let grid_h = heightmap.len();
-    let grid_w = heightmap[0].len();
-
-    let mut graph = Paths::new();
-    let mut index_to_node_id = Vec::new();
-    let mut node_id_s = 0.into();
-    let mut node_id_e = 0.into();
-
-    // initialize graph
-    for row in heightmap.iter() {
-        let mut node_map = Vec::new();
-        for node in row.iter() {
-            debug!("adding node '{}' to graph", node);
-            match node {
-                'S' => {
-                    debug!("found node S!");
-                    let node_id = graph.add_node('a');
-                    node_map.push(node_id);
-                    node_id_s = node_id;
-                }
-                'E' => {
-                    let node_id = graph.add_node('z');
-                    node_map.push(node_id);
-                    node_id_e = node_id;
-                }
-                _ => node_map.push(graph.add_node(*node)),
-            }
-        }
-        index_to_node_id.push(node_map);
-    }
-
-    // add graph edges
-    for i in 0..grid_h {
-        for j in 0..grid_w {
-            if j < grid_w - 1 {
-                add_edge_if_possible(&mut graph, &index_to_node_id, (i, j), (i, j + 1))
-                    .context("failed when adding edges")?;
-            }
-            if i < grid_h - 1 {
-                add_edge_if_possible(&mut graph, &index_to_node_id, (i, j), (i + 1, j))
-                    .context("failed when adding edges")?;
-            }
-        }
-    }
-
-    Ok((graph, node_id_s, node_id_e))
+fn find_position(heightmap: &HeightMap, node: char) -> Option<Position> {
+    heightmap
+        .iter()
+        .enumerate()
+        .find_map(|(i, row)| row.iter().position(|c| *c == node).map(|j| (i, j)))
 }
 
 fn char_to_int(c: char) -> i32 {
-    c as i32 - 'a' as i32
-}
-
-fn add_edge_if_possible(
-    graph: &mut Paths,
-    index_to_node_id: &Vec<Vec<NodeIndex>>,
-    (c_i, c_j): (usize, usize),
-    (d_i, d_j): (usize, usize),
-) -> Result<()> {
-    let c_id = *index_to_node_id
-        .get(c_i)
-        .ok_or(anyhow!("c row index out of bounds"))?
-        .get(c_j)
-        .ok_or(anyhow!("c col index out of bounds"))?;
-    let d_id = *index_to_node_id
-        .get(d_i)
-        .ok_or(anyhow!("d row index out of bounds"))?
-        .get(d_j)
-        .ok_or(anyhow!("d dol index out of bounds"))?;
-
-    let c_elevation = graph
-        .node_weight(c_id)
-        .ok_or(anyhow!("failed to access node elevation"))?
-        .to_owned();
-    let d_elevation = graph
-        .node_weight(d_id)
-        .ok_or(anyhow!("failed to access node elevation"))?
-        .to_owned();
-
-    let c_elevation_val = char_to_int(c_elevation);
-    let d_elevation_val = char_to_int(d_elevation);
-
-    if (d_elevation_val - c_elevation_val).abs() <= 1 {
-        // both directions
-        graph.add_edge(c_id, d_id, ());
-        graph.add_edge(d_id, c_id, ());
-        debug!(
-            "Added edge: ({:#?},{}) <--> ({:#?},{})",
-            c_id, c_elevation, d_id, d_elevation
-        );
-    } else if d_elevation_val < c_elevation_val {
-        // dest -> curr
-        graph.add_edge(d_id, c_id, ());
-        debug!(
-            "Added edge: ({:#?},{}) <--- ({:#?},{})",
-            c_id, c_elevation, d_id, d_elevation
-        );
-    } else {
-        // current -> dest
-        graph.add_edge(c_id, d_id, ());
-        debug!(
-            "Added edge: ({:#?},{}) ---> ({:#?},{})",
-            c_id, c_elevation, d_id, d_elevation
-        );
+    match c {
+        'S' => 0,
+        'E' => char_to_int('z'),
+        _ => c as i32 - 'a' as i32,
     }
-
-    Ok(())
 }
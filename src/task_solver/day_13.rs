@@ -2,14 +2,12 @@ use anyhow::{bail, Context, Result};
 
 use log::{debug, error, info};
 
-use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
-
-pub fn solve(_task: u8, input: String) -> Result<()> {
+use itertools::Itertools;
+use std::{collections::VecDeque, fmt, fs::File, io::BufReader, str::FromStr};
+
+use super::util::input::{self, Line, Paragraphs};
+
+pub fn solve(_task: u8, input: String) -> Result<String> {
     let parser = PacketParser::init(input).context("failed to instantiate parser")?;
 
     match _task {
@@ -19,10 +17,11 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
     }
 }
 
-fn solve_1(parser: PacketParser) -> Result<()> {
+fn solve_1(parser: PacketParser) -> Result<String> {
     let mut index_sum = 0u32;
 
-    for (i, (packet_0, packet_1)) in parser.enumerate() {
+    for (i, packet_pair) in parser.enumerate() {
+        let (packet_0, packet_1) = packet_pair?;
         debug!("found packet pair:\n\t{:?}\n\t{:?}", packet_0, packet_1);
         if packet_0 <= packet_1 {
             debug!("packets are in the right order!");
@@ -35,14 +34,16 @@ fn solve_1(parser: PacketParser) -> Result<()> {
         index_sum
     );
 
-    Ok(())
+    Ok(index_sum.to_string())
 }
 
-fn solve_2(parser: PacketParser) -> Result<()> {
-    let mut packet_list = parser
-        .map(|(p1, p2)| vec![p1, p2])
-        .flatten()
-        .collect::<Vec<Packet>>();
+fn solve_2(parser: PacketParser) -> Result<String> {
+    let mut packet_list = Vec::new();
+    for packet_pair in parser {
+        let (p1, p2) = packet_pair?;
+        packet_list.push(p1);
+        packet_list.push(p2);
+    }
 
     let sep_0 = Packet::LIST(vec![Packet::LIST(vec![Packet::INT(2)])]);
     let sep_1 = Packet::LIST(vec![Packet::LIST(vec![Packet::INT(6)])]);
@@ -70,7 +71,7 @@ fn solve_2(parser: PacketParser) -> Result<()> {
         sep_0_i * sep_1_i
     );
 
-    Ok(())
+    Ok((sep_0_i * sep_1_i).to_string())
 }
 
 #[derive(Debug, Eq, Clone)]
@@ -107,6 +108,15 @@ impl PartialEq for Packet {
     }
 }
 
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::INT(x) => write!(f, "{}", x),
+            Packet::LIST(packets) => write!(f, "[{}]", packets.iter().join(",")),
+        }
+    }
+}
+
 impl FromStr for Packet {
     type Err = ();
 
@@ -174,48 +184,73 @@ impl FromStr for Packet {
 }
 
 struct PacketParser {
-    in_reader: BufReader<File>,
-    line: String,
+    paragraphs: Paragraphs<BufReader<File>>,
 }
 
 impl PacketParser {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+        let paragraphs = input::read_lines(input)?.paragraphs();
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
+        Ok(PacketParser { paragraphs })
+    }
+}
 
-        Ok(PacketParser { in_reader, line })
+fn parse_packet_pair(paragraph: &[Line]) -> Result<(Packet, Packet)> {
+    if let [line_0, line_1] = paragraph {
+        Ok((line_0.parse()?, line_1.parse()?))
+    } else {
+        bail!(
+            "paragraph starting at {} doesn't contain exactly two packets",
+            paragraph[0]
+        )
     }
 }
 
 impl Iterator for PacketParser {
-    type Item = (Packet, Packet);
+    type Item = Result<(Packet, Packet)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut packet_pair = Vec::new();
-
-        while packet_pair.len() != 2 {
-            let bytes_read = self
-                .in_reader
-                .read_line(&mut self.line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 {
-                return None; // EOF
-            } else if self.line == "\n" {
-                continue;
-            }
+        Some(self.paragraphs.next()?.and_then(|p| parse_packet_pair(&p)))
+    }
+}
 
-            packet_pair.push(Packet::from_str(self.line.trim()).expect("failed to parse packet"));
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
 
-            self.line.clear();
-        }
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_13::Packet;
+
+    /// small values so that generated packets are often equal or share a prefix
+    fn packet() -> impl Strategy<Value = Packet> {
+        (0..4u32)
+            .prop_map(Packet::INT)
+            .prop_recursive(4, 32, 4, |inner| {
+                prop::collection::vec(inner, 0..4).prop_map(Packet::LIST)
+            })
+    }
 
-        let packet_1 = packet_pair.pop().unwrap();
-        let packet_0 = packet_pair.pop().unwrap();
+    fn list_packet() -> impl Strategy<Value = Packet> {
+        prop::collection::vec(packet(), 0..4).prop_map(Packet::LIST)
+    }
+
+    proptest! {
+        #[test]
+        fn print_parse_round_trip(p in list_packet()) {
+            let printed = p.to_string();
+            let parsed: Packet = printed.parse().unwrap();
+            prop_assert_eq!(parsed.to_string(), printed);
+        }
 
-        Some((packet_0, packet_1))
+        #[test]
+        fn total_order_laws(a in packet(), b in packet(), c in packet()) {
+            prop_assert_eq!(a.cmp(&a), Ordering::Equal);
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+            if a <= b && b <= c {
+                prop_assert!(a <= c);
+            }
+        }
     }
 }
@@ -5,12 +5,12 @@ use log::{debug, info};
 use std::{
     cmp,
     collections::{HashMap, VecDeque},
-    fs::File,
-    io::{BufRead, BufReader},
     ops::Range,
 };
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+use super::util::input;
+
+pub fn solve(task: u8, input: String) -> Result<String> {
     let mut rock_structure =
         RockStructure::init(input, task).context("failed to instantiate parser")?;
 
@@ -25,7 +25,7 @@ pub fn solve(task: u8, input: String) -> Result<()> {
         rock_structure.grains.len()
     );
 
-    Ok(())
+    Ok(rock_structure.grains.len().to_string())
 }
 
 struct RockStructure {
@@ -40,29 +40,15 @@ struct RockStructure {
 
 impl RockStructure {
     fn init(input: String, task: u8) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-
         let mut structures = HashMap::new();
         let mut max_y = 0u32;
         let mut min_x = 500u32;
         let mut max_x = 500u32;
 
         // parse cave structure
-        loop {
-            let bytes_read = in_reader
-                .read_line(&mut line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 || line == "\n" {
-                break; // EOF
-            }
-
+        for line in input::read_lines(input)?.section()? {
             let mut prev_point = None;
-            for point in line.trim().split(" -> ") {
+            for point in line.text.trim().split(" -> ") {
                 let coordinates = point.split(',').collect::<Vec<&str>>();
                 let x_coord = coordinates[0]
                     .parse::<u32>()
@@ -92,7 +78,6 @@ impl RockStructure {
                 }
                 prev_point = Some((x_coord, y_coord));
             }
-            line.clear();
         }
 
         // set initial path
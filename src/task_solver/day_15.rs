@@ -1,18 +1,24 @@
 use anyhow::{bail, Context, Result};
 
 use log::{debug, info};
-use regex::Regex;
 
-use std::{
-    cmp,
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{cmp, collections::HashSet};
 
-use super::util;
+use super::util::input;
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
+const RE_SENSOR: &str = r"Sensor at x=(?P<x_sensor>[-]?\d+), y=(?P<y_sensor>[-]?\d+): closest beacon is at x=(?P<x_beacon>[-]?\d+), y=(?P<y_beacon>[-]?\d+)";
+
+pub fn solve(task: u8, input: String) -> Result<String> {
+    solve_with(task, input, 2000000, (0, 4000000))
+}
+
+/// solves the task for the given row of interest (task 1) or search range of x and y (task 2)
+pub fn solve_with(
+    _task: u8,
+    input: String,
+    row: i32,
+    range_of_interest: (i32, i32),
+) -> Result<String> {
     let sensor_map = SensorMap::init(input).context("failed to instantiate parser")?;
 
     info!(
@@ -22,7 +28,7 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
 
     match _task {
         1 => {
-            let y = 2000000;
+            let y = row;
             let covered_ranges = sensor_map.get_row_coverage(y);
             let no_beacon_count = covered_ranges.iter().fold(0u32, |a, r| {
                 let mut res = a + r.0.abs_diff(r.1) + 1;
@@ -38,10 +44,9 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
                 "number of positions that cannot contain a beacon: {}",
                 no_beacon_count
             );
+            Ok(no_beacon_count.to_string())
         }
         2 => {
-            let range_of_interest = (0, 4000000);
-            // let range_of_interest = (0, 20);
             for y in range_of_interest.0..range_of_interest.1 + 1 {
                 debug!("checking line {} for positions that aren't covered", y);
                 let covered_ranges = sensor_map.get_row_coverage(y);
@@ -55,17 +60,16 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
                             "distress beacon found at ({},{}) - tuning frequency is {}",
                             r.0, y, tuning_frequency
                         );
-                        break;
+                        return Ok(tuning_frequency.to_string());
                     } else {
                         bail!("found range that wasn't entirely covered, but contained multiple elements: {:?}", not_covered);
                     }
                 }
             }
+            bail!("every position in the range of interest is covered by a sensor")
         }
         _ => bail!("task doesn't exist!"),
     }
-
-    Ok(())
 }
 
 type Coord = (i32, i32);
@@ -91,41 +95,17 @@ fn reaches_row(((_, y_sensor), radius): &Sensor, y: i32) -> bool {
 
 impl SensorMap {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-
         let mut sensors = Vec::new();
         let mut beacons = HashSet::new();
 
         // parse input file line-by-line
-        let re_sensor = Regex::new(r"Sensor at x=(?P<x_sensor>[-]?\d+), y=(?P<y_sensor>[-]?\d+): closest beacon is at x=(?P<x_beacon>[-]?\d+), y=(?P<y_beacon>[-]?\d+)").unwrap();
-        while in_reader
-            .read_line(&mut line)
-            .expect("Failed to read line in input file")
-            != 0
-            && line != "\n"
-        {
-            line = line.trim().to_owned();
-            debug!("parsing line {}", line);
-            let x_sensor = util::capture_and_parse(&re_sensor, &line, "x_sensor", &|s| {
-                s.parse::<i32>().unwrap()
-            });
-            let y_sensor = util::capture_and_parse(&re_sensor, &line, "y_sensor", &|s| {
-                s.parse::<i32>().unwrap()
-            });
-            let x_beacon = util::capture_and_parse(&re_sensor, &line, "x_beacon", &|s| {
-                s.parse::<i32>().unwrap()
-            });
-            let y_beacon = util::capture_and_parse(&re_sensor, &line, "y_beacon", &|s| {
-                s.parse::<i32>().unwrap()
-            });
+        for line in input::read_lines(input)?.section()? {
+            debug!("parsing {}", line);
+            let (x_sensor, y_sensor, x_beacon, y_beacon) = line
+                .captures(RE_SENSOR)?
+                .extract(["x_sensor", "y_sensor", "x_beacon", "y_beacon"])?;
             sensors.push(from_scan((x_sensor, y_sensor), (x_beacon, y_beacon)));
             beacons.insert((x_beacon, y_beacon));
-            line.clear();
         }
 
         Ok(SensorMap { sensors, beacons })
@@ -167,8 +147,8 @@ fn difference_with_range(range_list: &mut Vec<(i32, i32)>, r: &(i32, i32)) {
             if overlap.0 == curr_r.0 && overlap.1 == curr_r.1 {
                 // curr_r fully contained in r --> remove
                 false
-            } else if overlap.0 == r.0 && overlap.1 == r.1 {
-                // r fully contained in curr_r --> split
+            } else if overlap.0 > curr_r.0 && overlap.1 < curr_r.1 {
+                // r strictly inside curr_r --> split
                 to_push.push((r.1 + 1, curr_r.1));
                 curr_r.1 = overlap.0 - 1;
                 true
@@ -193,3 +173,68 @@ fn difference_with_list(range_list_lhs: &mut Vec<(i32, i32)>, range_list_rhs: &V
         difference_with_range(range_list_lhs, r);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_15::{difference_with_list, merge_into_list};
+
+    fn range() -> impl Strategy<Value = (i32, i32)> {
+        (-20..20, 0..10).prop_map(|(start, len)| (start, start + len))
+    }
+
+    fn covered(range_list: &[(i32, i32)]) -> BTreeSet<i32> {
+        range_list
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .collect()
+    }
+
+    fn merged(ranges: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let mut range_list = Vec::new();
+        for &r in ranges {
+            merge_into_list(&mut range_list, r);
+        }
+        range_list
+    }
+
+    #[test]
+    fn difference_touching_an_end() {
+        // used to be split into (-1, -1) and the empty range (5, 4)
+        let mut range_list = vec![(-1, 4)];
+        difference_with_list(&mut range_list, &vec![(0, 4)]);
+        assert_eq!(range_list, [(-1, -1)]);
+
+        let mut range_list = vec![(0, 5)];
+        difference_with_list(&mut range_list, &vec![(2, 3)]);
+        assert_eq!(range_list, [(0, 1), (4, 5)]);
+    }
+
+    proptest! {
+        #[test]
+        fn merge_covers_union(ranges in prop::collection::vec(range(), 0..8)) {
+            let range_list = merged(&ranges);
+            prop_assert_eq!(covered(&range_list), covered(&ranges));
+            // merged ranges never overlap
+            prop_assert_eq!(
+                covered(&range_list).len(),
+                range_list.iter().map(|r| (r.1 - r.0 + 1) as usize).sum::<usize>()
+            );
+        }
+
+        #[test]
+        fn difference_covers_set_difference(
+            lhs in prop::collection::vec(range(), 0..8),
+            rhs in prop::collection::vec(range(), 0..8),
+        ) {
+            let mut range_list = merged(&lhs);
+            difference_with_list(&mut range_list, &rhs);
+            prop_assert!(range_list.iter().all(|r| r.0 <= r.1), "invalid range in {:?}", range_list);
+            let expected: BTreeSet<i32> = covered(&lhs).difference(&covered(&rhs)).cloned().collect();
+            prop_assert_eq!(covered(&range_list), expected);
+        }
+    }
+}
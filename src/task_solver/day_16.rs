@@ -1,72 +1,64 @@
 use anyhow::{bail, Context, Result};
 
-use itertools::Itertools;
-use log::info;
-use regex::Regex;
+use log::{debug, info};
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
-    fs::File,
-    io::{BufRead, BufReader},
+    collections::{HashMap, HashSet, VecDeque},
     rc::Rc,
 };
 
-use super::util;
+use super::util::{input, memo::Memo, BitSet};
+
+const RE_VALVE: &str = r"Valve (?P<id>[A-Z]{2}) has flow rate=(?P<flow_rate>\d+); tunnel(?:s)? lead(?:s)? to valve(?:s)? (?P<tunnels>([A-Z]{2}(:?, )?)+)";
 
 type NodeRef = Rc<RefCell<Node>>;
-type State = (u32, String, BTreeSet<String>);
+type State = (u32, usize, BitSet);
 
 #[derive(Debug)]
 struct Node {
-    id: String,
+    /// position of the valve in the sets of closed valves
+    index: usize,
     flow_rate: u32,
     paths: Vec<(u32, NodeRef)>,
 }
 
-fn max_score(
-    node: &NodeRef,
-    t: u32,
-    closed: &mut BTreeSet<String>,
-    visited: &mut HashMap<State, u32>,
-) -> u32 {
-    let state = (t, node.borrow().id.to_owned(), closed.clone());
-    if visited.contains_key(&state) {
-        return visited[&state];
-    }
-
-    let mut score = 0;
-    for (path_len, next) in node.borrow().paths.iter() {
-        let next_id = next.borrow().id.to_owned();
-        if t > *path_len + 1 && closed.remove(&next_id) {
-            let next_score = (t - path_len - 1) * next.borrow().flow_rate
-                + max_score(next, t - path_len - 1, closed, visited);
-            closed.insert(next_id);
-            if next_score > score {
-                score = next_score;
+fn max_score(node: &NodeRef, t: u32, closed: BitSet, memo: &mut Memo<State, u32>) -> u32 {
+    memo.cached((t, node.borrow().index, closed), |memo, _| {
+        let mut score = 0;
+        for (path_len, next) in node.borrow().paths.iter() {
+            let next_index = next.borrow().index;
+            if t > *path_len + 1 && closed.contains(next_index) {
+                let mut still_closed = closed;
+                still_closed.remove(next_index);
+                let next_score = (t - path_len - 1) * next.borrow().flow_rate
+                    + max_score(next, t - path_len - 1, still_closed, memo);
+                if next_score > score {
+                    score = next_score;
+                }
             }
         }
-    }
-    visited.insert(state, score.clone());
-    score
+        score
+    })
 }
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let (start, mut id_list) = init(input).context("failed to instantiate parser")?;
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let (start, valves) = init(input).context("failed to instantiate parser")?;
     let score = match task {
-        1 => max_score(&start, 30, &mut id_list, &mut HashMap::new()),
+        1 => {
+            let mut memo = Memo::new();
+            let score = max_score(&start, 30, valves, &mut memo);
+            debug!("memo stats: {}", memo.stats());
+            score
+        }
         2 => {
-            let mut visited_states = HashMap::new();
+            let mut memo = Memo::new();
             let mut visited_sets = HashSet::new();
             let mut max = 0u32;
-            for mut subset in id_list
-                .iter()
-                .powerset()
-                .map(|subset| subset.into_iter().cloned().collect::<BTreeSet<String>>())
-            {
+            for subset in valves.subsets() {
                 if !visited_sets.contains(&subset) {
-                    let mut complement = id_list.difference(&subset).cloned().collect();
-                    let score = max_score(&start, 26, &mut complement, &mut visited_states)
-                        + max_score(&start, 26, &mut subset, &mut visited_states);
+                    let complement = valves - subset;
+                    let score = max_score(&start, 26, complement, &mut memo)
+                        + max_score(&start, 26, subset, &mut memo);
                     visited_sets.insert(subset);
                     visited_sets.insert(complement);
                     if score > max {
@@ -74,56 +66,47 @@ pub fn solve(task: u8, input: String) -> Result<()> {
                     }
                 }
             }
+            debug!("memo stats: {}", memo.stats());
             max
         }
         _ => bail!("task doesn't exist"),
     };
     info!("max released pressure: {}", score);
-    Ok(())
+    Ok(score.to_string())
 }
 
-fn init(input: String) -> Result<(NodeRef, BTreeSet<String>)> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
+fn init(input: String) -> Result<(NodeRef, BitSet)> {
     let mut valve_system = HashMap::new();
     let mut connections = HashMap::new();
 
-    let re_sensor = Regex::new(r"Valve (?P<id>[A-Z]{2}) has flow rate=(?P<flow_rate>\d+); tunnel(?:s)? lead(?:s)? to valve(?:s)? (?P<tunnels>([A-Z]{2}(:?, )?)+)").unwrap();
-    while in_reader
-        .read_line(&mut line)
-        .context("Failed to read line in input file")?
-        != 0
-        && line != "\n"
-    {
-        let id = util::capture_and_parse(&re_sensor, &line, "id", &|s| s.to_owned());
-        let flow_rate = util::capture_and_parse(&re_sensor, &line, "flow_rate", &|s| {
-            s.parse::<u32>().expect("failed to parse flow_rate")
-        });
-        let tunnels = util::capture_and_parse(&re_sensor, &line, "tunnels", &|s| {
-            s.split(", ").map(|s| s.to_owned()).collect::<Vec<String>>()
-        });
+    for line in input::read_lines(input)?.section()? {
+        let (id, flow_rate, tunnels): (String, u32, String) = line
+            .captures(RE_VALVE)?
+            .extract(["id", "flow_rate", "tunnels"])?;
+        let tunnels = tunnels
+            .split(", ")
+            .map(|s| s.to_owned())
+            .collect::<Vec<String>>();
         connections.insert(id.to_owned(), tunnels);
         if id == "AA" || flow_rate > 0 {
+            let index = valve_system.len();
+            if index >= BitSet::<u64>::WIDTH {
+                bail!("too many valves with non-zero flow rate");
+            }
             valve_system.insert(
                 id.to_owned(),
                 Rc::new(RefCell::new(Node {
-                    id: id.to_owned(),
+                    index,
                     flow_rate,
                     paths: vec![],
                 })),
             );
         }
-        line.clear();
     }
 
     Ok((
         connect(&valve_system, &connections),
-        valve_system.keys().cloned().collect(),
+        BitSet::full(valve_system.len()),
     ))
 }
 
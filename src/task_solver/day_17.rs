@@ -2,15 +2,12 @@ use anyhow::{bail, Context, Result};
 
 use log::{debug, info};
 
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    vec,
-};
+use std::vec;
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
-    let mut tetris_tower = TetrisTower::init(input).context("failed to instantiate parser")?;
+use super::util::{cycle, input, BitSet};
+
+pub fn solve(_task: u8, input: String) -> Result<String> {
+    let tetris_tower = TetrisTower::init(input).context("failed to instantiate parser")?;
 
     let n_iterations = match _task {
         1 => 2022,
@@ -18,55 +15,44 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
         _ => bail!("task doesn't exist!"),
     };
 
-    let (repeating_state, (i_0, h_0), (i_1, h_1)) =
-        find_repeating_sequence(&mut tetris_tower).context("failed to find repeating sequence")?;
+    // the state only holds the rows that rocks can still reach, so we only need to keep a single one in memory
+    let tower_cycle = cycle::find_cycle_brent(|| {
+        tetris_tower.clone().map(|(state, h)| {
+            debug!("jet: {}, rock: {}, h: {}", state.1, state.2, h);
+            let tower_height = h + state.0.len();
+            (state, tower_height)
+        })
+    })
+    .context("tetris tower iteration terminated before we could find a repeating sequence!")?;
 
-    let height = if n_iterations < i_0 {
-        tetris_tower.reset_state();
-        let (_, h) = tetris_tower.nth(n_iterations - 1).unwrap();
-        h + tetris_tower.tower.len()
-    } else {
-        let mut rem_iterations = n_iterations - i_0 - 1; // this many rocks still need to be dropped
-        let curr_height = h_0 + rem_iterations / (i_1 - i_0) * (h_1 - h_0);
-        rem_iterations = rem_iterations % (i_1 - i_0);
-        tetris_tower.reset_state_to(repeating_state, curr_height);
-        let (_, h) = tetris_tower.nth(rem_iterations - 1).unwrap();
-        h + tetris_tower.tower.len()
-    };
+    info!(
+        "found repeating state after {} iterations",
+        tower_cycle.start + tower_cycle.period
+    );
+
+    let height = tower_cycle
+        .extrapolate(n_iterations - 1)
+        .context("tower height doesn't fit into usize")?;
 
     info!(
         "tower is {} units tall after {} rocks have stopped falling",
         height, n_iterations
     );
 
-    Ok(())
+    Ok(height.to_string())
 }
 
-fn find_repeating_sequence(
-    tetris_tower: &mut TetrisTower,
-) -> Result<(State, (usize, usize), (usize, usize))> {
-    let mut seen_states = HashMap::new();
-    for (i, (state, h)) in tetris_tower.enumerate() {
-        debug!("i: {}, h: {}", i, h);
-        if !seen_states.contains_key(&state) {
-            seen_states.insert(state, (i, h));
-        } else {
-            info!("found repeating state after {} iterations", i);
-            let (i_prev, h_prev) = seen_states.get(&state).unwrap();
-            return Ok((state, (*i_prev, *h_prev), (i, h)));
-        }
-    }
-    bail!("tetris tower iteration terminated before we could find a repeating sequence!");
-}
-
-type State = (Vec<u8>, usize, usize);
+/// row of the chamber - bit 7 is the leftmost column, bit 1 the rightmost and bit 0 is unused
+type Row = BitSet<u8>;
+type State = (Vec<Row>, usize, usize);
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum Jet {
     LEFT,
     RIGHT,
 }
 
+#[derive(Clone)]
 enum TetrisBlock {
     HOR,
     PLUS,
@@ -75,13 +61,14 @@ enum TetrisBlock {
     SQUARE,
 }
 
+#[derive(Clone)]
 struct TetrisTower {
     /// list of jet directions to be iterated in falling rock simulation
     jet_pattern: Vec<Jet>,
     /// list of rock patterns to be iterated over
     rock_pattern: Vec<TetrisBlock>,
     /// list of bits - indicating height in units and how the tower is currently filled
-    tower: Vec<u8>,
+    tower: Vec<Row>,
     /// number of fallen rocks - used to index jet_pattern and decide next rock to drop
     rock_no: usize,
     /// number of passed jets - used to index jet_pattern and decide next jet to use
@@ -92,13 +79,6 @@ struct TetrisTower {
 
 impl TetrisTower {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-
         let mut jet_pattern = Vec::new();
         let rock_pattern = vec![
             TetrisBlock::HOR,
@@ -112,15 +92,11 @@ impl TetrisTower {
         let jet_no = 0;
         let height = 0;
 
-        if in_reader
-            .read_line(&mut line)
-            .expect("Failed to read input file")
-            == 0
-        {
-            bail!("input file is empty!");
-        }
+        let line = input::read_lines(input)?
+            .next()
+            .context("input file is empty!")??;
 
-        for c in line.trim().chars() {
+        for c in line.text.trim().chars() {
             jet_pattern.push(match c {
                 '<' => Jet::LEFT,
                 '>' => Jet::RIGHT,
@@ -138,9 +114,9 @@ impl TetrisTower {
         })
     }
 
-    fn initial_rock_formation(&self, block: &TetrisBlock) -> Vec<(usize, u8)> {
+    fn initial_rock_formation(&self, block: &TetrisBlock) -> Vec<(usize, Row)> {
         let x = self.tower.len() + 3;
-        match block {
+        let rows = match block {
             TetrisBlock::HOR => vec![(x, 0b00111100u8)],
             TetrisBlock::PLUS => vec![
                 (x, 0b00010000u8),
@@ -161,10 +137,17 @@ impl TetrisTower {
                 (x + 3, 0b00100000u8),
             ],
             TetrisBlock::SQUARE => vec![(x, 0b00110000u8), (x + 1, 0b00110000u8)],
-        }
+        };
+        rows.into_iter()
+            .map(|(x, row)| (x, Row::from_bits(row)))
+            .collect()
     }
 
-    fn move_by(&self, block: &Vec<(usize, u8)>, (x, y): (usize, i32)) -> Option<Vec<(usize, u8)>> {
+    fn move_by(
+        &self,
+        block: &Vec<(usize, Row)>,
+        (x, y): (usize, i32),
+    ) -> Option<Vec<(usize, Row)>> {
         let mut new_block = Vec::new();
         for (b_x, row) in block {
             if *b_x == 0 && x == 1 {
@@ -172,10 +155,14 @@ impl TetrisTower {
                 return None;
             }
             let next_x = b_x - x;
-            let next_row = if y < 0 { row << y.abs() } else { row >> y };
-            if (row & 0x80u8 != 0x00u8 && y < 0)
-                || (next_row & 0x01u8 != 0x00u8 && y > 0)
-                || (next_x < self.tower.len() && next_row & self.tower[next_x] != 0x00u8)
+            let next_row = if y < 0 {
+                *row << y.unsigned_abs() as usize
+            } else {
+                *row >> y as usize
+            };
+            if (row.contains(7) && y < 0)
+                || (next_row.contains(0) && y > 0)
+                || (next_x < self.tower.len() && !next_row.is_disjoint(&self.tower[next_x]))
             {
                 return None;
             }
@@ -184,43 +171,52 @@ impl TetrisTower {
         Some(new_block)
     }
 
-    fn update_tower(&mut self, mut block: Vec<(usize, u8)>) -> Result<()> {
-        block.sort_by(|(x1, _), (x2, _)| x1.cmp(x2));
-        let mut x_mod = 0;
+    fn update_tower(&mut self, block: Vec<(usize, Row)>) -> Result<()> {
         for (x, row) in block {
-            let curr_x = x - x_mod;
-            let do_split = if let Some(curr_row) = self.tower.get_mut(curr_x) {
-                if *curr_row & row != 0x00u8 {
+            if let Some(curr_row) = self.tower.get_mut(x) {
+                if !curr_row.is_disjoint(&row) {
                     bail!("block can't be added here!");
-                } else {
-                    *curr_row = *curr_row | row;
-                    *curr_row == 0b11111110u8 // split when row is full
                 }
+                *curr_row |= row;
             } else {
+                self.tower.resize(x, Row::new());
                 self.tower.push(row);
-                false
-            };
-            if do_split {
-                self.height += curr_x + 1;
-                self.tower = self.tower.split_off(curr_x + 1);
-                x_mod = x_mod + curr_x + 1;
             }
         }
+        self.trim();
         Ok(())
     }
 
-    fn reset_state_to(&mut self, (tower, jet_no, rock_no): State, height: usize) {
-        self.tower = tower;
-        self.jet_no = jet_no;
-        self.rock_no = rock_no;
-        self.height = height;
-    }
+    /// drops the rows below the lowest empty cell that is connected to the space above the tower -
+    /// falling rocks can't get there anymore
+    fn trim(&mut self) {
+        let top = self.tower.len();
+        let is_open = |x: usize, col: usize| x == top || !self.tower[x].contains(col);
+
+        let mut reached = vec![Row::new(); top + 1];
+        let mut stack: Vec<(usize, usize)> = (1..=7).map(|col| (top, col)).collect();
+        let mut lowest = top;
+        while let Some((x, col)) = stack.pop() {
+            if !is_open(x, col) || !reached[x].insert(col) {
+                continue;
+            }
+            lowest = lowest.min(x);
+            if x > 0 {
+                stack.push((x - 1, col));
+            }
+            if x < top {
+                stack.push((x + 1, col));
+            }
+            if col > 1 {
+                stack.push((x, col - 1));
+            }
+            if col < 7 {
+                stack.push((x, col + 1));
+            }
+        }
 
-    fn reset_state(&mut self) {
-        self.tower = Vec::new();
-        self.rock_no = 0;
-        self.jet_no = 0;
-        self.height = 0;
+        self.height += lowest;
+        self.tower.drain(..lowest);
     }
 
     fn get_identifier(&self) -> State {
@@ -2,18 +2,16 @@ use anyhow::{bail, Context, Result};
 
 use log::info;
 
-use std::{
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::collections::HashSet;
+
+use super::util::{input, search};
 
 type Coord = (i32, i32, i32);
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+pub fn solve(task: u8, input: String) -> Result<String> {
     let droplet = parse_input(input).context("failed to parse input")?;
 
-    match task {
+    let num_faces = match task {
         1 => {
             let num_faces = get_num_faces(&droplet);
 
@@ -21,6 +19,7 @@ pub fn solve(task: u8, input: String) -> Result<()> {
                 "The surface area of the scanned lava dropplet is {}",
                 num_faces
             );
+            num_faces
         }
         2 => {
             let num_outer_faces = get_num_outer_faces(&droplet);
@@ -29,30 +28,20 @@ pub fn solve(task: u8, input: String) -> Result<()> {
                 "The exterior surface area of the scanned lava dropplet is {}",
                 num_outer_faces
             );
+            num_outer_faces
         }
         _ => bail!("task doesn't exist!"),
-    }
+    };
 
-    Ok(())
+    Ok(num_faces.to_string())
 }
 
 fn parse_input(input: String) -> Result<HashSet<Coord>> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut droplet = HashSet::new();
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
+    for line in input::read_lines(input)?.section()? {
         let cube: Vec<i32> = line
+            .text
             .trim()
             .split(',')
             .map(|s| {
@@ -63,8 +52,6 @@ fn parse_input(input: String) -> Result<HashSet<Coord>> {
 
         let cube_coord = (cube[0], cube[1], cube[2]);
         droplet.insert(cube_coord);
-
-        line.clear();
     }
 
     Ok(droplet)
@@ -86,42 +73,28 @@ fn get_num_outer_faces(droplet: &HashSet<Coord>) -> u32 {
             curr_max
         }
     });
-
-    // walk along the outside of the droplet from there --> to_visit and visited set
-    let mut num_outer_faces = 0u32;
-    let mut visited = HashSet::new();
-    let mut to_visit = HashSet::new();
     let starting_cube = (outer_cube.0 + 1, outer_cube.1, outer_cube.2);
-    to_visit.insert(starting_cube);
-
-    while let Some(cube) = pop_from_set(&mut to_visit) {
-        let num_adjacent_faces = get_num_adjacent_cubes(droplet, &cube);
-        let mut neighbours = get_neighbours(&cube);
-        to_visit.extend(if num_adjacent_faces == 0 {
-            neighbours.retain(|c| {
-                !visited.contains(c)
-                    && !droplet.contains(c)
-                    && get_num_adjacent_cubes(droplet, c) != 0
-            });
-            neighbours
-        } else {
-            neighbours.retain(|c| !visited.contains(c) && !droplet.contains(c));
-            neighbours
-        });
-        visited.insert(cube);
-        num_outer_faces += num_adjacent_faces;
-    }
 
-    num_outer_faces
-}
-
-fn pop_from_set(set: &mut HashSet<Coord>) -> Option<Coord> {
-    if let Some(elem) = set.iter().next().cloned() {
-        set.remove(&elem);
-        Some(elem)
-    } else {
-        None
-    }
+    // walk along the outside of the droplet from there
+    let outer_cubes = search::reachable(
+        [starting_cube],
+        |cube| {
+            let mut neighbours = get_neighbours(cube);
+            if get_num_adjacent_cubes(droplet, cube) == 0 {
+                neighbours
+                    .retain(|c| !droplet.contains(c) && get_num_adjacent_cubes(droplet, c) != 0);
+            } else {
+                neighbours.retain(|c| !droplet.contains(c));
+            }
+            neighbours
+        },
+        |cube| *cube,
+    );
+
+    outer_cubes
+        .iter()
+        .map(|cube| get_num_adjacent_cubes(droplet, cube))
+        .sum()
 }
 
 fn get_neighbours(cube: &Coord) -> HashSet<Coord> {
@@ -1,16 +1,25 @@
 use anyhow::{bail, Context, Result};
 
 use log::{debug, info};
-use regex::Regex;
 
-use std::{
-    cmp,
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
+use std::cmp;
+
+use super::util::{
+    capture::{Captures, FromCaptures},
+    input,
+    memo::Memo,
 };
 
-use super::util;
+const RE_BLUEPRINT: &str = concat!(
+    r"Blueprint (?P<blueprint_id>\d+): ",
+    r"Each ore robot costs (?P<ore_robot>\d+) ore. ",
+    r"Each clay robot costs (?P<clay_robot>\d+) ore. ",
+    r"Each obsidian robot costs (?P<obsidian_robot_ore>\d+) ore and (?P<obsidian_robot_clay>\d+) clay. ",
+    r"Each geode robot costs (?P<geode_robot_ore>\d+) ore and (?P<geode_robot_obsidian>\d+) obsidian."
+);
+
+/// maximum number of cached search states per blueprint
+const MEMO_LIMIT: usize = 1 << 20;
 
 #[derive(Debug)]
 struct Blueprint {
@@ -18,42 +27,54 @@ struct Blueprint {
     robot_costs: Vec<Vec<u32>>, // cost in (ore, clay, obsidian)
 }
 
+impl FromCaptures for Blueprint {
+    fn from_captures(captures: &Captures) -> Result<Self> {
+        let (ore_robot, clay_robot) = captures.extract(["ore_robot", "clay_robot"])?;
+        let (obsidian_robot_ore, obsidian_robot_clay) =
+            captures.extract(["obsidian_robot_ore", "obsidian_robot_clay"])?;
+        let (geode_robot_ore, geode_robot_obsidian) =
+            captures.extract(["geode_robot_ore", "geode_robot_obsidian"])?;
+        Ok(Blueprint {
+            id: captures.get("blueprint_id")?,
+            robot_costs: vec![
+                vec![ore_robot, 0, 0],
+                vec![clay_robot, 0, 0],
+                vec![obsidian_robot_ore, obsidian_robot_clay, 0],
+                vec![geode_robot_ore, 0, geode_robot_obsidian],
+            ],
+        })
+    }
+}
+
 impl Blueprint {
+    /// depth-first search over the order in which robots are built - geodes are credited as soon as
+    /// a geode robot is built, and best is the most geodes found in any branch so far. Once a state
+    /// has been searched, nothing below it can beat best anymore, and as best only grows, the memo
+    /// just has to remember that bound to skip the state next time.
     fn max_geodes(
         &self,
-        state: State,
+        state: &State,
         max_time: u32,
-        seen_states: &mut HashMap<State, u32>,
-    ) -> u32 {
-        if let Some(max_score) = seen_states.get(&state) {
-            *max_score
-        } else {
-            let mut new_states = Vec::new();
-            for robot in 0..self.robot_costs.len() {
+        best: &mut u32,
+        memo: &mut Memo<State, u32>,
+    ) {
+        if memo.get(state).is_some() {
+            return;
+        }
+        let geodes = *state.resources.last().expect("state resources are empty");
+        *best = cmp::max(*best, geodes);
+
+        // even a new geode robot in every remaining minute couldn't beat the best branch
+        let rem_time = max_time - state.time;
+        if geodes + rem_time * rem_time.saturating_sub(1) / 2 > *best {
+            // geode robots first, so that good branches are found early
+            for robot in (0..self.robot_costs.len()).rev() {
                 if let Some(new_state) = state.build_robot(self, robot, max_time) {
-                    new_states.push(new_state);
+                    self.max_geodes(&new_state, max_time, best, memo);
                 }
             }
-
-            let max_score = if new_states.len() == 0 {
-                // can't build any more robots
-                let final_state = state.advance_by(max_time - state.time);
-                *final_state
-                    .resources
-                    .last()
-                    .expect("final state resources are empty")
-            } else {
-                let mut max_score = 0u32;
-                while let Some(s) = new_states.pop() {
-                    max_score = cmp::max(max_score, self.max_geodes(s, max_time, seen_states));
-                }
-                max_score
-            };
-
-            seen_states.insert(state, max_score);
-
-            max_score
         }
+        memo.insert(state.clone(), *best);
     }
 
     fn max_required_resource(&self, robot: usize) -> u32 {
@@ -128,7 +149,7 @@ impl State {
     }
 }
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
+pub fn solve(_task: u8, input: String) -> Result<String> {
     let blueprint_list = parse_input(input).context("failed to parse input")?;
 
     let (max_time, mut quality_level, num_blueprints) = match _task {
@@ -140,15 +161,19 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
     for i in 0..cmp::min(blueprint_list.len(), num_blueprints) {
         let blueprint = &blueprint_list[i];
         debug!("considering blueprint {:?}", blueprint);
-        let max_geodes = blueprint.max_geodes(
-            State {
+        let mut max_geodes = 0;
+        let mut memo = Memo::with_limit(MEMO_LIMIT);
+        blueprint.max_geodes(
+            &State {
                 time: 0u32,
                 resources: vec![0, 0, 0, 0],
                 robots: vec![1, 0, 0],
             },
             max_time,
-            &mut HashMap::new(),
+            &mut max_geodes,
+            &mut memo,
         );
+        debug!("memo stats: {}", memo.stats());
 
         info!(
             "largest number of geodes you could open with blueprint {} in {} minutes is: {}",
@@ -164,67 +189,13 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
 
     info!("quality level: {}", quality_level);
 
-    Ok(())
+    Ok(quality_level.to_string())
 }
 
 fn parse_input(input: String) -> Result<Vec<Blueprint>> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut blueprint_list = Vec::new();
-
-    let re_blueprint = Regex::new(r"Blueprint (?P<blueprint_id>\d+):").unwrap();
-    let re_ore_robot = Regex::new(r"Each ore robot costs (?P<ore_robot>\d+) ore.").unwrap();
-    let re_clay_robot = Regex::new(r"Each clay robot costs (?P<clay_robot>\d+) ore.").unwrap();
-    let re_obsidian_robot = Regex::new(r"Each obsidian robot costs (?P<obsidian_robot_ore>\d+) ore and (?P<obsidian_robot_clay>\d+) clay.").unwrap();
-    let re_geode_robot = Regex::new(r"Each geode robot costs (?P<geode_robot_ore>\d+) ore and (?P<geode_robot_obsidian>\d+) obsidian.").unwrap();
-
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        if !re_blueprint.is_match(&line)
-            || !re_ore_robot.is_match(&line)
-            || !re_clay_robot.is_match(&line)
-            || !re_obsidian_robot.is_match(&line)
-            || !re_geode_robot.is_match(&line)
-        {
-            bail!("line didn't contain full blueprint");
-        }
-
-        let parse_int = &|s: &str| {
-            s.parse::<u32>()
-                .expect(&format!("failed to parse int from {}", s))
-        };
-        let blueprint_id = util::capture_and_parse(&re_blueprint, &line, "blueprint_id", parse_int);
-        let ore_robot = util::capture_and_parse(&re_ore_robot, &line, "ore_robot", parse_int);
-        let clay_robot = util::capture_and_parse(&re_clay_robot, &line, "clay_robot", parse_int);
-        let obsidian_robot_ore =
-            util::capture_and_parse(&re_obsidian_robot, &line, "obsidian_robot_ore", parse_int);
-        let obsidian_robot_clay =
-            util::capture_and_parse(&re_obsidian_robot, &line, "obsidian_robot_clay", parse_int);
-        let geode_robot_ore =
-            util::capture_and_parse(&re_geode_robot, &line, "geode_robot_ore", parse_int);
-        let geode_robot_obsidian =
-            util::capture_and_parse(&re_geode_robot, &line, "geode_robot_obsidian", parse_int);
-
-        blueprint_list.push(Blueprint {
-            id: blueprint_id,
-            robot_costs: vec![
-                vec![ore_robot, 0, 0],
-                vec![clay_robot, 0, 0],
-                vec![obsidian_robot_ore, obsidian_robot_clay, 0],
-                vec![geode_robot_ore, 0, geode_robot_obsidian],
-            ],
-        });
-        line.clear();
+    for line in input::read_lines(input)?.section()? {
+        blueprint_list.push(line.captures(RE_BLUEPRINT)?.parse()?);
     }
-
     Ok(blueprint_list)
 }
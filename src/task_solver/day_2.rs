@@ -1,116 +1,345 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{fs::File, io::BufReader};
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info};
 
-use anyhow::{anyhow, Context, Result};
-use log::info;
+use super::util::{
+    input::{self, Lines},
+    BitSet,
+};
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+pub fn solve(task: u8, input: String) -> Result<String> {
     // initiate parser
     let parser = GuideParser::init(input).context("failed to instantiate parser")?;
 
+    let game = Game::rock_paper_scissors();
+    let interpretation = match task {
+        1 => Interpretation::shapes(&["A", "B", "C"], &["X", "Y", "Z"]),
+        2 => Interpretation::outcomes(&["A", "B", "C"], ["X", "Y", "Z"]),
+        _ => return Err(anyhow!("this task doesn't exist!")),
+    };
+
     let mut total_score = 0u32;
+    let mut opponent_moves = Vec::new();
 
     for round in parser {
-        total_score += match task {
-            1 => get_score_1(round.0.as_str(), round.1.as_str())
-                .context("failed to compute score for round")?,
-            2 => get_score_2(round.0.as_str(), round.1.as_str())
-                .context("failed to compute score for round")?,
-            _ => return Err(anyhow!("this task doesn't exist!")),
-        };
+        let round = round?;
+        let (opp, player) = interpretation
+            .resolve(&game, round.0.as_str(), round.1.as_str())
+            .context("failed to compute score for round")?;
+        total_score += game.score(player, opp);
+        opponent_moves.push(opp);
     }
 
+    let report = game.analyse(&opponent_moves);
+    debug!(
+        "achievable totals against these moves: best {}, worst {}",
+        report.best, report.worst
+    );
+
     info!("Strategy guide results in total score of {}", total_score);
 
-    Ok(())
+    Ok(total_score.to_string())
 }
 
-fn get_score_1(opp_choice: &str, player_choice: &str) -> Result<u32> {
-    let init_i: u32 = match opp_choice {
-        "A" => 1,
-        "B" => 0,
-        "C" => 2,
-        _ => return Err(anyhow!("Illegal opp choice {}", opp_choice)),
-    };
-    let index = match player_choice {
-        "X" => 0,
-        "Y" => 1,
-        "Z" => 2,
-        _ => return Err(anyhow!("Illegal player choice {}", player_choice)),
-    };
-    Ok(((init_i + index) % 3) * 3 + index + 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
 }
 
-fn get_score_2(opp_choice: &str, player_choice: &str) -> Result<u32> {
-    let mut choice_i = match opp_choice {
-        "A" => 0,
-        "B" => 1,
-        "C" => 2,
-        _ => return Err(anyhow!("Illegal opp choice {}", opp_choice)),
-    };
-    let result = match player_choice {
-        "X" => {
-            choice_i = (choice_i + 2) % 3;
-            0
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    pub name: String,
+    pub score: u32,
+}
+
+/// A game where each player picks a shape and a beats-relation decides the winner
+#[derive(Clone, Debug)]
+pub struct Game {
+    shapes: Vec<Shape>,
+    /// shapes beaten by each shape
+    beats: Vec<BitSet>,
+    /// scores for a loss, a draw and a win
+    outcome_scores: [u32; 3],
+}
+
+impl Game {
+    /// builds a game from (winner, loser) pairs of shape indices
+    pub fn new(shapes: Vec<Shape>, beats: &[(usize, usize)]) -> Result<Self> {
+        if shapes.len() > BitSet::<u64>::WIDTH {
+            bail!("games are limited to {} shapes", BitSet::<u64>::WIDTH);
         }
-        "Y" => 1,
-        "Z" => {
-            choice_i = (choice_i + 1) % 3;
-            2
+        let mut beat_sets = vec![BitSet::new(); shapes.len()];
+        for &(winner, loser) in beats {
+            if winner >= shapes.len() || loser >= shapes.len() {
+                bail!(
+                    "({}, {}) refers to a shape that doesn't exist",
+                    winner,
+                    loser
+                );
+            }
+            if winner == loser || beat_sets[loser].contains(winner) {
+                bail!(
+                    "{} and {} can't beat each other",
+                    shapes[winner].name,
+                    shapes[loser].name
+                );
+            }
+            beat_sets[winner].insert(loser);
         }
-        _ => return Err(anyhow!("Illegal player choice {}", player_choice)),
-    };
-    Ok(result * 3 + choice_i + 1)
+        Ok(Game {
+            shapes,
+            beats: beat_sets,
+            outcome_scores: [0, 3, 6],
+        })
+    }
+
+    /// every shape beats the len/2 shapes in front of it, wrapping around - shapes score 1, 2, ...
+    pub fn cyclic(names: &[&str]) -> Result<Self> {
+        let n = names.len();
+        if n % 2 == 0 {
+            bail!("cyclic games need an odd number of shapes, got {}", n);
+        }
+        let shapes = names
+            .iter()
+            .zip(1..)
+            .map(|(name, score)| Shape {
+                name: name.to_string(),
+                score,
+            })
+            .collect();
+        let beats: Vec<_> = (0..n)
+            .flat_map(|winner| (1..=n / 2).map(move |k| (winner, (winner + n - k) % n)))
+            .collect();
+        Game::new(shapes, &beats)
+    }
+
+    pub fn rock_paper_scissors() -> Self {
+        Game::cyclic(&["Rock", "Paper", "Scissors"]).expect("rock paper scissors is cyclic")
+    }
+
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        Game::cyclic(&["Rock", "Spock", "Paper", "Lizard", "Scissors"])
+            .expect("rock paper scissors lizard spock is cyclic")
+    }
+
+    /// replaces the scores for a loss, a draw and a win
+    pub fn with_outcome_scores(self, outcome_scores: [u32; 3]) -> Self {
+        Game {
+            outcome_scores,
+            ..self
+        }
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    pub fn shape(&self, name: &str) -> Option<usize> {
+        self.shapes.iter().position(|shape| shape.name == name)
+    }
+
+    pub fn outcome(&self, player: usize, opp: usize) -> Outcome {
+        if self.beats[player].contains(opp) {
+            Outcome::Win
+        } else if self.beats[opp].contains(player) {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// score of the player for a single round
+    pub fn score(&self, player: usize, opp: usize) -> u32 {
+        self.outcome_scores[self.outcome(player, opp) as usize] + self.shapes[player].score
+    }
+
+    /// highest scoring shape that leads to the outcome against the opponent's shape
+    pub fn response(&self, opp: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.shapes.len())
+            .filter(|&player| self.outcome(player, opp) == outcome)
+            .max_by_key(|&player| self.shapes[player].score)
+    }
+
+    /// best and worst totals the player could achieve against the opponent's moves
+    pub fn analyse(&self, opponent_moves: &[usize]) -> StrategyReport {
+        let mut report = StrategyReport::default();
+        for &opp in opponent_moves {
+            let scores = (0..self.shapes.len()).map(|player| (self.score(player, opp), player));
+            let (best, best_response) = scores.clone().max().unwrap_or_default();
+            report.best += best;
+            report.worst += scores.map(|(score, _)| score).min().unwrap_or_default();
+            report.best_responses.push(best_response);
+        }
+        report
+    }
+}
+
+/// Result of analysing a sequence of opponent moves
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StrategyReport {
+    pub best: u32,
+    pub worst: u32,
+    /// shape to play in each round to achieve the best total
+    pub best_responses: Vec<usize>,
+}
+
+/// What the second column of the strategy guide stands for
+#[derive(Clone, Debug)]
+pub enum Column {
+    /// the i-th symbol is the i-th shape of the game
+    Shapes(Vec<String>),
+    /// symbols for a loss, a draw and a win
+    Outcomes([String; 3]),
+}
+
+/// Maps the symbols of the strategy guide to shapes of a game
+#[derive(Clone, Debug)]
+pub struct Interpretation {
+    /// symbols of the opponent's shapes, in the order of the game's shapes
+    opponent: Vec<String>,
+    player: Column,
+}
+
+impl Interpretation {
+    pub fn shapes(opponent: &[&str], player: &[&str]) -> Self {
+        Interpretation {
+            opponent: opponent.iter().map(|s| s.to_string()).collect(),
+            player: Column::Shapes(player.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    pub fn outcomes(opponent: &[&str], player: [&str; 3]) -> Self {
+        Interpretation {
+            opponent: opponent.iter().map(|s| s.to_string()).collect(),
+            player: Column::Outcomes(player.map(|s| s.to_owned())),
+        }
+    }
+
+    /// returns the shapes of the opponent and the player for a round of the guide
+    pub fn resolve(
+        &self,
+        game: &Game,
+        opp_choice: &str,
+        player_choice: &str,
+    ) -> Result<(usize, usize)> {
+        let opp = symbol_index(&self.opponent, opp_choice, game)
+            .ok_or(anyhow!("Illegal opp choice {}", opp_choice))?;
+        let player = match &self.player {
+            Column::Shapes(symbols) => symbol_index(symbols, player_choice, game),
+            Column::Outcomes(symbols) => match symbols.iter().position(|s| s == player_choice) {
+                Some(i) => Some(game.response(opp, Outcome::ALL[i]).ok_or(anyhow!(
+                    "no shape leads to a {:?} against {}",
+                    Outcome::ALL[i],
+                    game.shapes[opp].name
+                ))?),
+                None => None,
+            },
+        }
+        .ok_or(anyhow!("Illegal player choice {}", player_choice))?;
+        Ok((opp, player))
+    }
+}
+
+fn symbol_index(symbols: &[String], symbol: &str, game: &Game) -> Option<usize> {
+    symbols
+        .iter()
+        .position(|s| s == symbol)
+        .filter(|&i| i < game.shapes.len())
 }
 
 struct GuideParser {
-    in_reader: BufReader<File>,
-    line: String,
+    lines: Lines<BufReader<File>>,
 }
 
 impl GuideParser {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+        let lines = input::read_lines(input)?;
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
-
-        Ok(GuideParser { in_reader, line })
+        Ok(GuideParser { lines })
     }
 }
 
 impl Iterator for GuideParser {
-    type Item = (String, String);
+    type Item = Result<(String, String)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let bytes_read = self
-            .in_reader
-            .read_line(&mut self.line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 {
-            return None; // EOF
-        } else if self.line == "\n" {
-            return None; // No more predictions to parse
+        let line = match self.lines.next()? {
+            Ok(line) if line.is_blank() => return None, // No more predictions to parse
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let mut round = line.text.split_whitespace();
+        match (round.next(), round.next(), round.next()) {
+            (Some(opp_choice), Some(my_choice), None) => {
+                Some(Ok((opp_choice.to_owned(), my_choice.to_owned())))
+            }
+            _ => Some(Err(anyhow!(
+                "{} doesn't contain exactly two predictions",
+                line
+            ))),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::day_2::{Game, Interpretation, Outcome};
+
+    #[test]
+    fn rock_paper_scissors_test() {
+        let game = Game::rock_paper_scissors();
+        let guide = [("A", "Y"), ("B", "X"), ("C", "Z")];
+        let total = |interpretation: Interpretation| {
+            guide
+                .iter()
+                .map(|(opp, player)| {
+                    let (opp, player) = interpretation.resolve(&game, opp, player).unwrap();
+                    game.score(player, opp)
+                })
+                .sum::<u32>()
+        };
+        assert_eq!(
+            total(Interpretation::shapes(&["A", "B", "C"], &["X", "Y", "Z"])),
+            15
+        );
+        assert_eq!(
+            total(Interpretation::outcomes(&["A", "B", "C"], ["X", "Y", "Z"])),
+            12
+        );
+
+        let report = game.analyse(&[0, 1, 2]);
+        assert_eq!((report.best, report.worst), (8 + 9 + 7, 3 + 1 + 2));
+        assert_eq!(report.best_responses, vec![1, 2, 0]);
+    }
 
-        let mut round = self.line.trim().split_whitespace();
-        let opp_choice = round
-            .next()
-            .expect("line didn't contain prediction for opponent's choice")
-            .to_owned();
-        let my_choice = round
-            .next()
-            .expect("line didn't contain prediction for players's choice")
-            .to_owned();
-        if let Some(rem_prediction) = round.next() {
-            panic!("line contained another element: {}", rem_prediction);
+    #[test]
+    fn lizard_spock_test() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let shape = |name| game.shape(name).unwrap();
+        assert_eq!(
+            game.outcome(shape("Spock"), shape("Scissors")),
+            Outcome::Win
+        );
+        assert_eq!(game.outcome(shape("Lizard"), shape("Spock")), Outcome::Win);
+        assert_eq!(game.outcome(shape("Rock"), shape("Paper")), Outcome::Loss);
+        assert_eq!(game.outcome(shape("Paper"), shape("Paper")), Outcome::Draw);
+        // every shape beats exactly two others
+        for player in 0..5 {
+            let wins = (0..5).filter(|&opp| game.outcome(player, opp) == Outcome::Win);
+            assert_eq!(wins.count(), 2);
         }
 
-        self.line.clear();
-        Some((opp_choice, my_choice))
+        let scored = game.clone().with_outcome_scores([0, 1, 2]);
+        assert_eq!(scored.score(shape("Lizard"), shape("Paper")), 4 + 2);
+        assert!(Game::cyclic(&["A", "B"]).is_err());
+        assert!(Game::new(game.shapes().to_vec(), &[(0, 1), (1, 0)]).is_err());
     }
 }
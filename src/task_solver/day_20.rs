@@ -2,13 +2,11 @@ use anyhow::{bail, Context, Result};
 
 use log::info;
 
-use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::collections::VecDeque;
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+use super::util::input;
+
+pub fn solve(task: u8, input: String) -> Result<String> {
     let (mut val_list, mut index_list) = parse_input(input).context("failed to parse input")?;
     let len = val_list.len();
 
@@ -54,7 +52,7 @@ pub fn solve(task: u8, input: String) -> Result<()> {
         first, second, third, sum
     );
 
-    Ok(())
+    Ok(sum.to_string())
 }
 
 fn find_destination(move_val: i64, len: usize, curr_i: usize) -> usize {
@@ -69,27 +67,68 @@ fn find_destination(move_val: i64, len: usize, curr_i: usize) -> usize {
 }
 
 fn parse_input(input: String) -> Result<(Vec<i64>, VecDeque<usize>)> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut val_list = Vec::new();
     let mut index_list = VecDeque::new();
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
+    for line in input::read_lines(input)?.section()? {
         index_list.push_back(val_list.len());
-        val_list.push(line.trim().parse().unwrap());
-
-        line.clear();
+        val_list.push(line.parse()?);
     }
 
     Ok((val_list, index_list))
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_20::find_destination;
+
+    /// moves the element one position at a time, wrapping around the ends
+    fn move_step_by_step(list: &mut [usize], mut i: usize, move_val: i64) {
+        let len = list.len();
+        for _ in 0..move_val.unsigned_abs() {
+            let next = if move_val > 0 {
+                (i + 1) % len
+            } else {
+                (i + len - 1) % len
+            };
+            list.swap(i, next);
+            i = next;
+        }
+    }
+
+    /// rotates the circular list so it starts at the given element
+    fn normalised(list: &[usize], first: usize) -> Vec<usize> {
+        let start = list.iter().position(|&v| v == first).unwrap();
+        list[start..]
+            .iter()
+            .chain(&list[..start])
+            .cloned()
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn destination_in_range(move_val in any::<i32>(), len in 2..100usize, curr_i in 0..100usize) {
+            let curr_i = curr_i % len;
+            let dest_i = find_destination(move_val as i64, len, curr_i);
+            prop_assert!(dest_i < len - 1);
+            prop_assert_eq!((dest_i as i64 - curr_i as i64 - move_val as i64).rem_euclid(len as i64 - 1), 0);
+        }
+
+        #[test]
+        fn destination_matches_step_by_step(move_val in -50..50i64, len in 2..12usize, curr_i in 0..12usize) {
+            let curr_i = curr_i % len;
+            let mut expected: Vec<usize> = (0..len).collect();
+            move_step_by_step(&mut expected, curr_i, move_val);
+
+            let mut list: Vec<usize> = (0..len).collect();
+            let moved = list.remove(curr_i);
+            list.insert(find_destination(move_val, len, curr_i), moved);
+
+            let first = (curr_i + 1) % len; // any element that didn't move
+            prop_assert_eq!(normalised(&list, first), normalised(&expected, first));
+        }
+    }
+}
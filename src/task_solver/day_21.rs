@@ -1,17 +1,14 @@
 use anyhow::{bail, Context, Result};
 
-use log::info;
-use regex::Regex;
+use log::{debug, info};
 
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::collections::HashMap;
 
-use super::util;
+use super::util::{input, memo::Memo};
 
-#[derive(Clone)]
+const RE_MONKEY: &str = r"(?P<monkey_id>[a-z]{4}): (?:(?P<val>\d+)|(?P<lhs>[a-z]{4}) (?P<op>[+\-*\\/]) (?P<rhs>[a-z]{4}))";
+
+#[derive(Clone, Debug)]
 enum Operation {
     ADD(String, String),
     SUB(String, String),
@@ -83,106 +80,89 @@ impl Operation {
 struct Monkey {
     value: Option<i64>,
     op: Option<Operation>,
-    lhs: Option<i64>,
-    rhs: Option<i64>,
 }
 
+/// computes the value a monkey yells - None if it depends on the human
 fn get_val(
-    monkey_map: &mut HashMap<String, Monkey>,
+    monkey_map: &HashMap<String, Monkey>,
+    memo: &mut Memo<String, Option<i64>>,
     monkey_id: &str,
     need_human: bool,
 ) -> Option<i64> {
     if need_human && monkey_id == "humn" {
         None
     } else {
-        let m = monkey_map.get(monkey_id).unwrap();
-        if let Some(val) = m.value {
-            Some(val)
-        } else {
-            let op = m.op.clone().unwrap();
-            let lhs = get_val(monkey_map, op.lhs(), need_human);
-            let rhs = get_val(monkey_map, op.rhs(), need_human);
-            let m = monkey_map.get_mut(monkey_id).unwrap();
-            if lhs.is_some() && rhs.is_some() {
-                // cache result
-                let val = Some(op.compute(lhs.unwrap(), rhs.unwrap()));
-                m.value = val;
-                val
+        memo.cached(monkey_id.to_owned(), |memo, monkey_id| {
+            let m = monkey_map.get(monkey_id).unwrap();
+            if let Some(val) = m.value {
+                Some(val)
             } else {
-                m.lhs = lhs;
-                m.rhs = rhs;
-                None
+                let op = m.op.as_ref().unwrap();
+                let lhs = get_val(monkey_map, memo, op.lhs(), need_human);
+                let rhs = get_val(monkey_map, memo, op.rhs(), need_human);
+                Some(op.compute(lhs?, rhs?))
             }
-        }
+        })
     }
 }
 
-fn solve_chain(monkey_map: &HashMap<String, Monkey>, monkey_id: &str, res: i64) -> i64 {
+fn solve_chain(
+    monkey_map: &HashMap<String, Monkey>,
+    memo: &mut Memo<String, Option<i64>>,
+    monkey_id: &str,
+    res: i64,
+) -> i64 {
     if monkey_id == "humn" {
         res
     } else {
-        let m = monkey_map.get(monkey_id).unwrap();
-        let op = m.op.clone().unwrap();
-        let next_res = op.invert(res, m.lhs, m.rhs);
-        let next_m = if m.lhs.is_none() { op.lhs() } else { op.rhs() };
-        solve_chain(monkey_map, next_m, next_res)
+        let op = monkey_map.get(monkey_id).unwrap().op.as_ref().unwrap();
+        let lhs = get_val(monkey_map, memo, op.lhs(), true);
+        let rhs = get_val(monkey_map, memo, op.rhs(), true);
+        let next_res = op.invert(res, lhs, rhs);
+        let next_m = if lhs.is_none() { op.lhs() } else { op.rhs() };
+        solve_chain(monkey_map, memo, next_m, next_res)
     }
 }
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let mut monkey_map = parse_input(input).context("failed to parse input")?;
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let monkey_map = parse_input(input).context("failed to parse input")?;
+    let mut memo = Memo::new();
 
-    let root_val = get_val(&mut monkey_map, "root", task == 2);
+    let root_val = get_val(&monkey_map, &mut memo, "root", task == 2);
 
-    if let Some(val) = root_val {
+    let answer = if let Some(val) = root_val {
         info!("computed root value: {}", val);
+        val
     } else {
-        let root = monkey_map.get("root").unwrap();
-        let (m, res) = if root.lhs.is_none() {
-            (root.op.as_ref().unwrap().lhs(), root.rhs.unwrap())
-        } else {
-            (root.op.as_ref().unwrap().rhs(), root.lhs.unwrap())
+        let op = monkey_map.get("root").unwrap().op.as_ref().unwrap();
+        let lhs = get_val(&monkey_map, &mut memo, op.lhs(), true);
+        let rhs = get_val(&monkey_map, &mut memo, op.rhs(), true);
+        let (m, res) = match (lhs, rhs) {
+            (None, Some(rhs)) => (op.lhs(), rhs),
+            (Some(lhs), None) => (op.rhs(), lhs),
+            _ => bail!("exactly one side of root's equality test has to depend on humn"),
         };
-        let humn_val = solve_chain(&monkey_map, m, res);
+        let humn_val = solve_chain(&monkey_map, &mut memo, m, res);
         info!("number to yell to pass root's equality test: {}", humn_val);
-    }
+        humn_val
+    };
+    debug!("memo stats: {}", memo.stats());
 
-    Ok(())
+    Ok(answer.to_string())
 }
 
 fn parse_input(input: String) -> Result<HashMap<String, Monkey>> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut monkeys = HashMap::new();
-    let re_monkey = Regex::new(r"(?P<monkey_id>[a-z]{4}): (?:(?P<val>\d+)|(?P<lhs>[a-z]{4}) (?P<op>[+\-*\\/]) (?P<rhs>[a-z]{4}))").unwrap();
-
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        let monkey_id = util::capture_and_parse(&re_monkey, &line, "monkey_id", &|s| s.to_owned());
-        let monkey = if let Some(val) =
-            util::try_capture_and_parse(&re_monkey, &line, "val", &|s| {
-                s.parse::<i64>().expect("failed to parse value to i64")
-            }) {
+    for line in input::read_lines(input)?.section()? {
+        let captures = line.captures(RE_MONKEY)?;
+        let monkey_id: String = captures.get("monkey_id")?;
+        let monkey = if let Some(val) = captures.try_get("val")? {
             Monkey {
                 value: Some(val),
                 op: None,
-                lhs: None,
-                rhs: None,
             }
         } else {
-            let lhs = util::capture_and_parse(&re_monkey, &line, "lhs", &|s| s.to_owned());
-            let rhs = util::capture_and_parse(&re_monkey, &line, "rhs", &|s| s.to_owned());
-            let op =
-                util::capture_and_parse(&re_monkey, &line, "op", &|s| s.chars().next().unwrap());
+            let (lhs, op, rhs): (String, char, String) = captures.extract(["lhs", "op", "rhs"])?;
             let monkey_op = match op {
                 '+' => Operation::ADD(lhs, rhs),
                 '-' => Operation::SUB(lhs, rhs),
@@ -193,15 +173,44 @@ fn parse_input(input: String) -> Result<HashMap<String, Monkey>> {
             Monkey {
                 value: None,
                 op: Some(monkey_op),
-                lhs: None,
-                rhs: None,
             }
         };
 
         monkeys.insert(monkey_id.to_owned(), monkey);
-
-        line.clear();
     }
 
     Ok(monkeys)
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_21::Operation;
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        let (x, y) = ("lhs".to_owned(), "rhs".to_owned());
+        prop_oneof![
+            Just(Operation::ADD(x.clone(), y.clone())),
+            Just(Operation::SUB(x.clone(), y.clone())),
+            Just(Operation::MUL(x.clone(), y.clone())),
+            Just(Operation::DIV(x, y)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn invert_recovers_operands(op in operation(), lhs in -10000..10000i64, rhs in -10000..10000i64) {
+            // monkeys only divide without remainder, and never by zero
+            let lhs = match op {
+                Operation::DIV(..) => lhs * rhs,
+                _ => lhs,
+            };
+            prop_assume!(!matches!(op, Operation::MUL(..) | Operation::DIV(..)) || (lhs != 0 && rhs != 0));
+
+            let res = op.compute(lhs, rhs);
+            prop_assert_eq!(op.invert(res, Some(lhs), None), rhs);
+            prop_assert_eq!(op.invert(res, None, Some(rhs)), lhs);
+        }
+    }
+}
@@ -4,9 +4,10 @@ use log::{debug, info};
 use num::integer::gcd;
 
 use std::collections::{HashMap, HashSet};
-use std::io::{BufRead, BufReader};
 use std::vec;
-use std::{cell::RefCell, collections::VecDeque, fs::File, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use super::util::input::{self, Line};
 
 type NodeRef = Option<Rc<RefCell<Node>>>;
 type Grid = Vec<Vec<NodeRef>>;
@@ -38,7 +39,7 @@ impl Node {
     }
 }
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+pub fn solve(task: u8, input: String) -> Result<String> {
     let (mut node, mut steps) = parse_input(input, task).context("failed to parse input")?;
 
     debug!(
@@ -79,29 +80,20 @@ pub fn solve(task: u8, input: String) -> Result<()> {
         final_password
     );
 
-    Ok(())
+    Ok(final_password.to_string())
 }
 
 fn parse_input(input: String, task: u8) -> Result<(Rc<RefCell<Node>>, VecDeque<(u8, u32)>)> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
+    let mut lines = input::read_lines(input)?;
 
     let mut grid = Vec::new();
     let mut obstacles = HashSet::new();
     let mut path = VecDeque::new();
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        let mut row = Vec::with_capacity(line.trim_end().len() - 1);
-        for (i, c) in line.trim_end().chars().enumerate() {
+    // first section: map of the board
+    for line in lines.section()? {
+        let mut row = Vec::with_capacity(line.text.trim_end().len());
+        for (i, c) in line.text.trim_end().chars().enumerate() {
             let coord = (grid.len(), i);
             match c {
                 '.' => {
@@ -121,9 +113,7 @@ fn parse_input(input: String, task: u8) -> Result<(Rc<RefCell<Node>>, VecDeque<(
             }
         }
         grid.push(row);
-        line.clear();
     }
-    line.clear();
 
     let start_node = find_start(&grid).expect("failed to find starting node");
     match task {
@@ -135,34 +125,36 @@ fn parse_input(input: String, task: u8) -> Result<(Rc<RefCell<Node>>, VecDeque<(
         _ => bail!("task doesn't exist!"),
     }
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        debug!("parsing line {}", line);
+    // second section: path to follow
+    for line in lines.section()? {
+        debug!("parsing {}", line);
         let mut orientation_change = 0u8;
         let mut curr_steps = String::new();
-        for c in line.chars() {
+        for c in line.text.trim().chars() {
             if c.is_ascii_digit() {
                 curr_steps.push(c);
             } else {
-                path.push_front((orientation_change, curr_steps.parse::<u32>().unwrap()));
+                path.push_front((orientation_change, line_steps(&line, &curr_steps)?));
                 curr_steps.clear();
                 match c {
                     'L' => orientation_change = 3,
                     'R' => orientation_change = 1,
-                    _ => (),
+                    _ => bail!("unknown direction '{}' in {}", c, line),
                 }
             }
         }
-        line.clear();
+        path.push_front((orientation_change, line_steps(&line, &curr_steps)?));
     }
 
     Ok((start_node, path))
 }
 
+fn line_steps(line: &Line, steps: &str) -> Result<u32> {
+    steps
+        .parse::<u32>()
+        .context(format!("failed to parse number of steps in {}", line))
+}
+
 fn connect<W>(grid: Grid, obstacles: &HashSet<(usize, usize)>, wrap: W)
 where
     W: Fn(&Grid, i32, i32, usize, &(i32, i32)) -> NodeRef,
@@ -346,3 +338,48 @@ fn walk_edges(
             .collect(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_22::compute_dest_coord;
+
+    /// offset of the tile at position i along edge d of a face - directions are right, down, left, up
+    fn edge_offset(side_len: usize, d: usize, i: usize) -> (usize, usize) {
+        match d {
+            0 => (i, side_len - 1),
+            1 => (side_len - 1, i),
+            2 => (i, 0),
+            _ => (0, i),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn walking_back_returns_to_start(
+            side_len in 1..50usize,
+            i in 0..50usize,
+            (from_d, to_d) in (0..4usize, 0..4usize),
+            walk_clockwise in any::<bool>(),
+            (from_face, to_face) in ((0..4usize, 0..4usize), (0..4usize, 0..4usize)),
+        ) {
+            let i = i % side_len;
+            let from_face = (from_face.0 * side_len, from_face.1 * side_len);
+            let to_face = (to_face.0 * side_len, to_face.1 * side_len);
+            let offset = edge_offset(side_len, from_d, i);
+
+            let dest = compute_dest_coord(side_len, to_face, offset, from_d, to_d, walk_clockwise);
+            // the destination lies on edge to_d of the neighbouring face
+            let dest_offset = (dest.0 - to_face.0, dest.1 - to_face.1);
+            prop_assert!(dest_offset.0 < side_len && dest_offset.1 < side_len);
+            prop_assert!((0..side_len).any(|j| edge_offset(side_len, to_d, j) == dest_offset));
+
+            // crossing the same edge in the opposite direction leads back to the start - walk_clockwise
+            // refers to the edge that is entered, so it flips if only one of the edges is right or up
+            let walk_back_clockwise = walk_clockwise ^ (from_d % 3 == 0) ^ (to_d % 3 == 0);
+            let back = compute_dest_coord(side_len, from_face, dest_offset, to_d, from_d, walk_back_clockwise);
+            prop_assert_eq!(back, (from_face.0 + offset.0, from_face.1 + offset.1));
+        }
+    }
+}
@@ -6,15 +6,13 @@ use log::{debug, info};
 use std::{
     cmp,
     collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufRead, BufReader},
 };
 
-use super::util::Point;
+use super::util::{input, Point};
 
 type Coordinate = Point<i32>;
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
+pub fn solve(_task: u8, input: String) -> Result<String> {
     let (mut elf_positions, mut left_top, mut right_bottom) =
         parse_input(input).context("failed to parse input")?;
     let directions: Vec<_> = vec![(-1, 0), (1, 0), (0, -1), (0, 1)]
@@ -25,6 +23,7 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
     debug!("== Initial State ==");
     debug_region(&elf_positions, left_top, right_bottom);
 
+    let mut answers = (None, None);
     for r in 0.. {
         if r == 10 {
             let num_empty_tiles = (right_bottom.0.abs_diff(left_top.0) + 1)
@@ -35,6 +34,7 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
                 "number of empty ground tiles after 10 rounds: {}",
                 num_empty_tiles
             );
+            answers.0 = Some(num_empty_tiles);
         }
 
         let mut position_updates: HashMap<Coordinate, Option<Coordinate>> = HashMap::new();
@@ -78,11 +78,18 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
 
         if !did_update {
             info!("first round where no elf moved: {}", r + 1);
+            answers.1 = Some(r as u32 + 1);
             break;
         }
     }
 
-    Ok(())
+    match _task {
+        1 => answers.0,
+        2 => answers.1,
+        _ => bail!("task doesn't exist!"),
+    }
+    .map(|answer| answer.to_string())
+    .context("elves stopped moving before the 10th round")
 }
 
 fn debug_region(
@@ -100,7 +107,6 @@ fn debug_region(
             }
         }
         debug!("{}", line);
-        line.clear();
     }
 }
 
@@ -127,26 +133,13 @@ fn has_neighbours(
 }
 
 fn parse_input(input: String) -> Result<(HashSet<Coordinate>, (i32, i32), (i32, i32))> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut elf_positions = HashSet::new();
     let mut left_top = None;
     let mut right_bottom = None;
 
-    let mut curr_row = 0;
-
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        for (curr_col, c) in line.trim().chars().enumerate() {
+    for line in input::read_lines(input)?.section()? {
+        let curr_row = line.number as i32 - 1;
+        for (curr_col, c) in line.text.trim().chars().enumerate() {
             if c == '#' {
                 elf_positions.insert(Coordinate::of_tuple((curr_row, curr_col as i32)));
                 if let Some((x, y)) = left_top.as_mut() {
@@ -163,9 +156,6 @@ fn parse_input(input: String) -> Result<(HashSet<Coordinate>, (i32, i32), (i32,
                 }
             }
         }
-
-        line.clear();
-        curr_row += 1
     }
 
     Ok((elf_positions, left_top.unwrap(), right_bottom.unwrap()))
@@ -2,69 +2,50 @@ use anyhow::{bail, Context, Result};
 
 use log::{debug, info};
 
-use std::{
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
-use super::util::Point;
+use super::util::{input, search, Point};
 
 type Coordinate = Point<u32>;
 type Blizzard = Option<i8>;
 type BlizzardList = Vec<Vec<Blizzard>>;
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
+pub fn solve(_task: u8, input: String) -> Result<String> {
     let (hor_blizzards, vert_blizzards, start, end) =
         parse_input(input).context("failed to parse input")?;
 
+    // blizzards repeat after this many rounds --> only need to visit each field once per cycle
     let cycles = num::integer::lcm(hor_blizzards.len() - 2, vert_blizzards.len() - 2) as u32;
-    let mut to_visit = HashSet::new(); // LIFO queue to simulate BFS for Dijkstra
-    let mut to_visit_next = HashSet::new();
-    let mut visited = HashSet::new();
-    let mut found_start = false;
-    let mut found_end = false;
-
-    to_visit.insert(start);
-    'outer: for s in 0.. {
-        'inner: for p in to_visit.drain() {
-            debug!("field {:?} in round {}", p, s);
-            for n in find_moves(&hor_blizzards, &vert_blizzards, &start, &end, &p, s).into_iter() {
-                if !visited.contains(&(n, (s + 1) % cycles)) {
-                    if n == end {
-                        if !found_end {
-                            found_end = true;
-                            info!("shortest path to end has length: {}", s + 1);
-                            visited.clear();
-                            to_visit_next.clear();
-                            to_visit_next.insert(end);
-                            break 'inner;
-                        } else if found_start {
-                            info!(
-                                "shortest path there, and back, and there again has length: {}",
-                                s + 1
-                            );
-                            break 'outer;
-                        }
-                    } else if n == start && !found_start && found_end {
-                        found_start = true;
-                        info!("made it back to start");
-                        visited.clear();
-                        to_visit_next.clear();
-                        to_visit_next.insert(start);
-                        break 'inner;
-                    }
-                    to_visit_next.insert(n);
-                }
-            }
-            visited.insert((p, s % cycles));
-        }
-        let tmp = to_visit;
-        to_visit = to_visit_next;
-        to_visit_next = tmp;
-    }
 
-    Ok(())
+    let shortest_path = |from: &Coordinate, to: &Coordinate, round: u32| {
+        search::bfs(
+            [(*from, round)],
+            |&(p, s)| {
+                debug!("field {:?} in round {}", p, s);
+                find_moves(&hor_blizzards, &vert_blizzards, &start, &end, &p, s)
+                    .into_iter()
+                    .map(move |n| (n, s + 1))
+            },
+            |&(p, s)| (p, s % cycles),
+            |(p, _)| p == to,
+        )
+        .map(|path| round + path.cost as u32)
+        .context("couldn't find a path through the blizzards")
+    };
+
+    let there = shortest_path(&start, &end, 0)?;
+    info!("shortest path to end has length: {}", there);
+    let back = shortest_path(&end, &start, there)?;
+    info!("made it back to start");
+    let there_again = shortest_path(&start, &end, back)?;
+    info!(
+        "shortest path there, and back, and there again has length: {}",
+        there_again
+    );
+
+    match _task {
+        1 => Ok(there.to_string()),
+        2 => Ok(there_again.to_string()),
+        _ => bail!("task doesn't exist!"),
+    }
 }
 
 fn find_moves(
@@ -137,25 +118,13 @@ fn get_blizz(blizzard_list: &Vec<Blizzard>, p: usize, round: i32) -> Blizzard {
 }
 
 fn parse_input(input: String) -> Result<(BlizzardList, BlizzardList, Coordinate, Coordinate)> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut hor_blizzards = Vec::new();
     let mut vert_blizzards = Vec::new();
     let mut start = None;
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
+    for line in input::read_lines(input)?.section()? {
         let mut row = Vec::new();
-        for (curr_col, c) in line.trim().chars().enumerate() {
+        for (curr_col, c) in line.text.trim().chars().enumerate() {
             let col = if let Some(v_blizzard) = vert_blizzards.get_mut(curr_col) {
                 v_blizzard
             } else {
@@ -197,7 +166,6 @@ fn parse_input(input: String) -> Result<(BlizzardList, BlizzardList, Coordinate,
         if row.len() > 1 {
             hor_blizzards.push(row);
         }
-        line.clear();
     }
 
     let end = Coordinate::of_tuple((
@@ -1,13 +1,11 @@
 use anyhow::{bail, Context, Result};
 use log::{debug, info};
 
-use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::collections::VecDeque;
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
+use super::util::input;
+
+pub fn solve(_task: u8, input: String) -> Result<String> {
     let snafu_numbers = parse_input(input).context("failed to parse input")?;
 
     let mut sum = 0u64;
@@ -15,12 +13,10 @@ pub fn solve(_task: u8, input: String) -> Result<()> {
         sum += snafu_to_decimal(snafu).context("failed to parse snafu number")?;
     }
 
-    info!(
-        "SNAFU number to supply to Bob's console: {}",
-        dec_to_snafu(sum).context("failed to convert dec to snafu")?
-    );
+    let snafu = dec_to_snafu(sum).context("failed to convert dec to snafu")?;
+    info!("SNAFU number to supply to Bob's console: {}", snafu);
 
-    Ok(())
+    Ok(snafu)
 }
 
 fn snafu_to_decimal(snafu: &str) -> Result<u64> {
@@ -72,23 +68,10 @@ fn dec_to_snafu(mut dec: u64) -> Result<String> {
 }
 
 fn parse_input(input: String) -> Result<Vec<String>> {
-    // open input file
-    let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-    // uses a reader buffer
-    let mut in_reader = BufReader::new(in_file);
-    let mut line = String::new();
-
     let mut snafu_numbers = Vec::new();
 
-    while in_reader
-        .read_line(&mut line)
-        .expect("Failed to read input file")
-        != 0
-        && line != "\n"
-    {
-        snafu_numbers.push(line.trim().to_owned());
-        line.clear();
+    for line in input::read_lines(input)?.section()? {
+        snafu_numbers.push(line.text.trim().to_owned());
     }
 
     Ok(snafu_numbers)
@@ -1,109 +1,229 @@
-use core::panic;
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{fs::File, io::BufReader};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, info};
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+use super::util::{
+    input::{self, Line, Lines},
+    GrowableBitSet,
+};
+
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let (group_size, compartments) = match task {
+        1 => (1, 2),
+        2 => (3, 1),
+        _ => bail!("task doesn't exist!"),
+    };
     let rucksack_parser =
-        RucksackParser::init(input, task).context("failed to instantiate parser")?;
+        RucksackParser::init(input, group_size, compartments, Alphabet::default())
+            .context("failed to instantiate parser")?;
 
     let mut item_sum = 0;
-    for i in rucksack_parser {
-        let prio = get_prio(i);
-        item_sum += prio;
-        debug!("Overlapping item: {} -> {}", i, prio);
+    for group in rucksack_parser {
+        let group = group?;
+        debug!(
+            "Overlapping items of group starting in line {}: {:?}",
+            group.first_line,
+            group.common_items()
+        );
+        item_sum += group.priority_sum();
     }
 
     info!(
         "Sum of the priorities of all erroneously-sorted items: {}",
         item_sum
     );
-    Ok(())
+    Ok(item_sum.to_string())
+}
+
+/// The items that can be packed - an item's priority is its position in the alphabet, starting at 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    items: Vec<char>,
 }
 
-fn get_prio(i: char) -> u16 {
-    if i.is_lowercase() {
-        i as u16 - 'a' as u16 + 1
-    } else {
-        i as u16 - 'A' as u16 + 27
+impl Alphabet {
+    pub fn new(items: &str) -> Result<Self> {
+        let items: Vec<char> = items.chars().collect();
+        for (i, c) in items.iter().enumerate() {
+            if items[..i].contains(c) {
+                bail!("item {:?} appears twice in the alphabet", c);
+            }
+        }
+        Ok(Alphabet { items })
+    }
+
+    pub fn priority(&self, item: char) -> Option<usize> {
+        self.items.iter().position(|&c| c == item).map(|i| i + 1)
+    }
+
+    pub fn item(&self, priority: usize) -> Option<char> {
+        self.items.get(priority.checked_sub(1)?).copied()
     }
 }
 
-fn slices_common_item(rucksack_slices: &[&str]) -> Option<char> {
-    if let Some(first) = rucksack_slices.first() {
-        let others = &rucksack_slices[1..];
-        first
-            .chars()
-            .find(|&c| others.iter().all(|s| s.contains(c)))
-    } else {
-        None
+/// a-z with priorities 1 through 26, A-Z with priorities 27 through 52
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet {
+            items: ('a'..='z').chain('A'..='Z').collect(),
+        }
     }
 }
 
-struct RucksackParser {
-    in_reader: BufReader<File>,
-    line: String,
-    task: u8,
+/// The items that every compartment of every rucksack in a group has in common
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// line of the group's first rucksack
+    pub first_line: usize,
+    /// priorities of the common items
+    pub common: GrowableBitSet,
+    alphabet: Alphabet,
 }
 
-impl RucksackParser {
-    fn init(input: String, task: u8) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+impl Group {
+    pub fn priority_sum(&self) -> usize {
+        self.common.iter().sum()
+    }
+
+    /// common items in order of their priority
+    pub fn common_items(&self) -> Vec<char> {
+        self.common
+            .iter()
+            .filter_map(|prio| self.alphabet.item(prio))
+            .collect()
+    }
+}
+
+/// returns the priorities of the items that are contained in every slice
+fn slices_common_items(rucksack_slices: &[&[usize]]) -> GrowableBitSet {
+    rucksack_slices
+        .iter()
+        .map(|s| s.iter().copied().collect::<GrowableBitSet>())
+        .reduce(|common, items| common.intersection(&items))
+        .unwrap_or_default()
+}
+
+pub struct RucksackParser {
+    lines: Lines<BufReader<File>>,
+    /// number of rucksacks per group
+    group_size: usize,
+    /// number of equally sized compartments per rucksack
+    compartments: usize,
+    alphabet: Alphabet,
+}
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
+impl RucksackParser {
+    pub fn init(
+        input: String,
+        group_size: usize,
+        compartments: usize,
+        alphabet: Alphabet,
+    ) -> Result<Self> {
+        if group_size == 0 || compartments == 0 {
+            bail!("groups and rucksacks can't be empty");
+        }
+        let lines = input::read_lines(input)?;
 
         Ok(RucksackParser {
-            in_reader,
-            line,
-            task,
+            lines,
+            group_size,
+            compartments,
+            alphabet,
+        })
+    }
+
+    /// priorities of the items in the rucksack
+    fn parse_rucksack(&self, line: &Line) -> Result<Vec<usize>> {
+        let prios = line
+            .text
+            .trim()
+            .chars()
+            .map(|c| {
+                self.alphabet
+                    .priority(c)
+                    .ok_or(anyhow!("{} contains unknown item {:?}", line, c))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if prios.len() % self.compartments != 0 {
+            bail!(
+                "{} can't be split into {} equally sized compartments",
+                line,
+                self.compartments
+            );
+        }
+        Ok(prios)
+    }
+
+    fn parse_group(&self, lines: &[Line]) -> Result<Group> {
+        let rucksacks = lines
+            .iter()
+            .map(|line| self.parse_rucksack(line))
+            .collect::<Result<Vec<_>>>()?;
+        let rucksack_slices: Vec<&[usize]> = rucksacks
+            .iter()
+            .flat_map(|prios| prios.chunks((prios.len() / self.compartments).max(1)))
+            .collect();
+        let common = slices_common_items(&rucksack_slices);
+        if common.is_empty() {
+            bail!(
+                "Rucksack compartments didn't contain overlapping item: {}",
+                lines[0]
+            );
+        }
+        Ok(Group {
+            first_line: lines[0].number,
+            common,
+            alphabet: self.alphabet.clone(),
         })
     }
 }
 
 impl Iterator for RucksackParser {
-    type Item = char;
+    type Item = Result<Group>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while !match self.task {
-            1 => self.line.split_terminator('\n').count() == 1,
-            2 => self.line.split_terminator('\n').count() == 3,
-            _ => panic!("task doesn't exist!"),
-        } {
-            let bytes_read = self
-                .in_reader
-                .read_line(&mut self.line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 {
-                return None; // EOF
-            } else if self.line == "\n" {
-                return None; // No more rucksacks to parse
+        let mut rucksacks = Vec::with_capacity(self.group_size);
+        while rucksacks.len() < self.group_size {
+            match self.lines.next() {
+                Some(Ok(line)) if !line.is_blank() => rucksacks.push(line),
+                Some(Err(e)) => return Some(Err(e)),
+                _ if rucksacks.is_empty() => return None, // No more rucksacks to parse
+                _ => {
+                    return Some(Err(anyhow!(
+                        "group starting in {} only has {} rucksacks",
+                        rucksacks[0],
+                        rucksacks.len()
+                    )))
+                }
             }
         }
 
-        let rucksack_slices = match self.task {
-            1 => {
-                let len = self.line.len();
-                vec![&self.line[..len / 2], &self.line[len / 2..]]
-            }
-            2 => {
-                let tmp_vec: Vec<&str> = self.line.split_terminator('\n').collect();
-                debug!("rucksack contains items: {:?}", tmp_vec);
-                tmp_vec
-            }
-            _ => panic!("task doesn't exist!"),
-        };
+        Some(self.parse_group(&rucksacks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::day_3::{slices_common_items, Alphabet};
+
+    #[test]
+    fn common_items_test() {
+        let alphabet = Alphabet::new("abc0123456789").unwrap();
+        assert_eq!(alphabet.priority('0'), Some(4));
+        assert_eq!(alphabet.priority('z'), None);
+        assert_eq!(alphabet.item(13), Some('9'));
+        assert!(Alphabet::new("abca").is_err());
 
-        let shared_item = slices_common_item(rucksack_slices.as_slice())
-            .expect("Rucksack compartments didn't contain overlapping item!");
+        let default = Alphabet::default();
+        assert_eq!(default.priority('p'), Some(16));
+        assert_eq!(default.priority('L'), Some(38));
 
-        self.line.clear();
-        Some(shared_item)
+        // priorities beyond a single word, repeated over long slices
+        let a: Vec<usize> = [1, 70, 200, 5000].repeat(1000);
+        let b: Vec<usize> = [70, 3, 5000].repeat(1000);
+        let c: Vec<usize> = [5000, 70, 9].repeat(1000);
+        let common = slices_common_items(&[&a, &b, &c]);
+        assert_eq!(common.iter().collect::<Vec<_>>(), vec![70, 5000]);
     }
 }
@@ -1,19 +1,26 @@
 use std::{
+    collections::BTreeSet,
     fs::File,
-    io::{BufRead, BufReader},
-    ops::Range,
+    io::BufReader,
+    ops::{Range, RangeInclusive},
 };
 
 use anyhow::{anyhow, Context, Result};
 use log::{debug, info};
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+use super::util::input::{self, Line, Lines};
+
+pub fn solve(task: u8, input: String) -> Result<String> {
     // initiate parser
     let parser = RangePairParser::init(input).context("failed to instantiate parser")?;
 
     let mut total_score = 0u32;
+    let mut roster = Vec::new();
 
     for range_pair in parser {
+        let range_pair = range_pair?;
+        roster.push(range_pair.0.start..=range_pair.0.end);
+        roster.push(range_pair.1.start..=range_pair.1.end);
         total_score += match task {
             1 => get_score_1(range_pair),
             2 => get_score_2(range_pair),
@@ -21,9 +28,16 @@ pub fn solve(task: u8, input: String) -> Result<()> {
         };
     }
 
+    let roster = Roster::new(roster);
+    let (max_overlap, sections) = roster.most_covered();
+    debug!(
+        "at most {} elves cover the same section, in sections {:?}",
+        max_overlap, sections
+    );
+
     info!("Strategy guide results in total score of {}", total_score);
 
-    Ok(())
+    Ok(total_score.to_string())
 }
 
 fn get_score_1((range_1, range_2): (Range<u32>, Range<u32>)) -> u32 {
@@ -45,66 +59,196 @@ fn get_score_2((range_1, range_2): (Range<u32>, Range<u32>)) -> u32 {
         0
     }
 }
+
+/// The section assignments of all elves - elf i is assigned to the i-th range
+pub struct Roster {
+    assignments: Vec<RangeInclusive<u32>>,
+    /// maximal runs of sections covered by the same number of elves, in ascending order
+    coverage: Vec<(RangeInclusive<u32>, usize)>,
+}
+
+impl Roster {
+    pub fn new(assignments: Vec<RangeInclusive<u32>>) -> Self {
+        // sweep over the boundaries - an assignment counts from its start up to and including its end
+        let mut events: Vec<(u64, i64)> = assignments
+            .iter()
+            .filter(|r| !r.is_empty())
+            .flat_map(|r| [(*r.start() as u64, 1), (*r.end() as u64 + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut coverage: Vec<(RangeInclusive<u32>, usize)> = Vec::new();
+        let mut count = 0i64;
+        for (i, &(pos, delta)) in events.iter().enumerate() {
+            count += delta;
+            let next = match events.get(i + 1) {
+                Some(&(next, _)) if next > pos => next,
+                _ => continue, // more events at the same position
+            };
+            if count > 0 {
+                let section = pos as u32..=(next - 1) as u32;
+                match coverage.last_mut() {
+                    Some((last, n)) if *n == count as usize && *last.end() as u64 + 1 == pos => {
+                        *last = *last.start()..=*section.end()
+                    }
+                    _ => coverage.push((section, count as usize)),
+                }
+            }
+        }
+
+        Roster {
+            assignments,
+            coverage,
+        }
+    }
+
+    pub fn assignments(&self) -> &[RangeInclusive<u32>] {
+        &self.assignments
+    }
+
+    /// runs of sections covered by at least one elf, together with the number of elves covering them
+    pub fn coverage(&self) -> &[(RangeInclusive<u32>, usize)] {
+        &self.coverage
+    }
+
+    /// maximum number of elves covering the same section, and the sections where that happens
+    pub fn most_covered(&self) -> (usize, Vec<RangeInclusive<u32>>) {
+        let max = self.coverage.iter().map(|(_, n)| *n).max().unwrap_or(0);
+        let sections = self
+            .coverage
+            .iter()
+            .filter(|(_, n)| max > 0 && *n == max)
+            .map(|(r, _)| r.clone())
+            .collect();
+        (max, sections)
+    }
+
+    /// sections within the bounds that no elf covers
+    pub fn uncovered(&self, bounds: RangeInclusive<u32>) -> Vec<RangeInclusive<u32>> {
+        let mut uncovered = Vec::new();
+        let mut next = *bounds.start() as u64;
+        for (section, _) in self.coverage.iter() {
+            let (start, end) = (*section.start() as u64, *section.end() as u64);
+            if start > *bounds.end() as u64 {
+                break;
+            }
+            if start > next {
+                uncovered.push(next as u32..=(start - 1) as u32);
+            }
+            next = next.max(end + 1);
+        }
+        if next <= *bounds.end() as u64 && !bounds.is_empty() {
+            uncovered.push(next as u32..=*bounds.end());
+        }
+        uncovered
+    }
+
+    /// for each elf, the other elves whose assignments overlap with its own, in ascending order
+    pub fn overlaps(&self) -> Vec<Vec<usize>> {
+        let mut order: Vec<usize> = (0..self.assignments.len())
+            .filter(|&elf| !self.assignments[elf].is_empty())
+            .collect();
+        order.sort_unstable_by_key(|&elf| *self.assignments[elf].start());
+
+        let mut overlaps = vec![Vec::new(); self.assignments.len()];
+        // assignments that started already, ordered by their end
+        let mut active: BTreeSet<(u32, usize)> = BTreeSet::new();
+        for elf in order {
+            let start = *self.assignments[elf].start();
+            while let Some(&(end, other)) = active.first() {
+                if end >= start {
+                    break;
+                }
+                active.remove(&(end, other));
+            }
+            for &(_, other) in active.iter() {
+                overlaps[elf].push(other);
+                overlaps[other].push(elf);
+            }
+            active.insert((*self.assignments[elf].end(), elf));
+        }
+        for others in overlaps.iter_mut() {
+            others.sort_unstable();
+        }
+        overlaps
+    }
+}
+
 struct RangePairParser {
-    in_reader: BufReader<File>,
-    line: String,
+    lines: Lines<BufReader<File>>,
 }
 
 impl RangePairParser {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+        let lines = input::read_lines(input)?;
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
-
-        Ok(RangePairParser { in_reader, line })
+        Ok(RangePairParser { lines })
     }
 }
 
-fn parse_range(range: &str) -> Range<u32> {
+fn parse_range(range: &str) -> Result<Range<u32>> {
     debug!("parsing range {}", range);
-    let range_split: Vec<&str> = range.split('-').collect();
-    if range_split.len() == 2 {
-        Range {
-            start: range_split[0]
-                .parse::<u32>()
-                .expect("failed to parse start of range"),
-            end: range_split[1]
-                .parse::<u32>()
-                .expect("failed to parse end of range"),
-        }
-    } else {
-        panic!("Input range didn't contain exactly 2 elements");
-    }
+    let (start, end) = range
+        .split_once('-')
+        .ok_or(anyhow!("Input range didn't contain exactly 2 elements"))?;
+    Ok(Range {
+        start: start
+            .parse::<u32>()
+            .context("failed to parse start of range")?,
+        end: end.parse::<u32>().context("failed to parse end of range")?,
+    })
+}
+
+fn parse_range_pair(line: &Line) -> Result<(Range<u32>, Range<u32>)> {
+    debug!("parsing {}", line);
+    let (range_1, range_2) = line
+        .text
+        .trim()
+        .split_once(',')
+        .ok_or(anyhow!("{} didn't contain exactly 2 range elements", line))?;
+    Ok((
+        parse_range(range_1).context(format!("failed to parse {}", line))?,
+        parse_range(range_2).context(format!("failed to parse {}", line))?,
+    ))
 }
 
 impl Iterator for RangePairParser {
-    type Item = (Range<u32>, Range<u32>);
+    type Item = Result<(Range<u32>, Range<u32>)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let bytes_read = self
-            .in_reader
-            .read_line(&mut self.line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 {
-            return None; // EOF
-        } else if self.line == "\n" {
-            return None; // No more predictions to parse
-        }
-
-        debug!("parsing line {}", self.line);
-
-        let range_pair: Vec<&str> = self.line.trim().split(',').collect();
-        let range_pair_parsed;
-        if range_pair.len() == 2 {
-            range_pair_parsed = (parse_range(range_pair[0]), parse_range(range_pair[1]));
-        } else {
-            panic!("line didn't contain exactly 2 range elemens");
+        match self.lines.next()? {
+            Ok(line) if line.is_blank() => None, // No more predictions to parse
+            Ok(line) => Some(parse_range_pair(&line)),
+            Err(e) => Some(Err(e)),
         }
+    }
+}
 
-        self.line.clear();
-        Some(range_pair_parsed)
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::day_4::Roster;
+
+    #[test]
+    fn roster_test() {
+        let roster = Roster::new(vec![2..=4, 6..=8, 3..=7, 12..=12, 4..=4]);
+        assert_eq!(
+            roster.coverage(),
+            &[
+                (2..=2, 1),
+                (3..=3, 2),
+                (4..=4, 3),
+                (5..=5, 1),
+                (6..=7, 2),
+                (8..=8, 1),
+                (12..=12, 1)
+            ]
+        );
+        assert_eq!(roster.most_covered(), (3, vec![4..=4]));
+        assert_eq!(roster.uncovered(0..=13), vec![0..=1, 9..=11, 13..=13]);
+        assert_eq!(roster.uncovered(5..=8), vec![]);
+        assert_eq!(
+            roster.overlaps(),
+            vec![vec![2, 4], vec![2], vec![0, 1, 4], vec![], vec![0, 2]]
+        );
     }
 }
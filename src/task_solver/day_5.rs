@@ -1,137 +1,479 @@
-use core::panic;
-use std::{
-    collections::{hash_map::RandomState, HashMap, VecDeque},
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
-use anyhow::{anyhow, Context, Result};
+use std::{cmp, fmt, fs::File, io::BufReader};
+
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, info};
-use regex::Regex;
 
-pub fn solve(task: u8, input: String) -> Result<()> {
+use super::util::input::{self, Line, Lines};
+
+const RE_MOVE: &str = r"move (?P<num>\d+) from (?P<from>\d+) to (?P<to>\d+)";
+
+pub fn solve(task: u8, input: String) -> Result<String> {
     // initiate parser
-    let mut crate_stacks = HashMap::new();
-    let parser = CargoCraneParser::init(input, &mut crate_stacks, task)
-        .context("failed to instantiate parser")?;
-
-    debug!("Initialized cargo stack: {:?}", parser.crate_stacks);
-
-    if parser.last().is_some() {
-        debug!("final stack: {:?}", crate_stacks);
-        let mut final_top = String::from("");
-        for i in 1..10 {
-            final_top.push(
-                crate_stacks
-                    .get(&i)
-                    .expect(format!("crate stack {} is missing!", i).as_str())
-                    .back()
-                    .expect(format!("crate stack {} is empty!", i).as_str())
-                    .to_owned(),
-            );
+    let mut parser = CargoCraneParser::init(input).context("failed to instantiate parser")?;
+
+    debug!("Initialized cargo stack:\n{}", parser.crate_stacks);
+
+    let crane: Box<dyn Crane> = match task {
+        1 => Box::new(CrateMover9000),
+        2 => Box::new(CrateMover9001),
+        _ => bail!("task doesn't exist!"),
+    };
+    let mut replay = Replay::new(crane.as_ref(), std::mem::take(&mut parser.crate_stacks));
+
+    for crane_move in parser {
+        let crane_move = crane_move?;
+        let crate_stacks = replay.apply(crane_move)?;
+        debug!("after {}:\n{}", crane_move, crate_stacks);
+    }
+
+    if !replay.history().is_empty() {
+        let crate_stacks = replay.stacks();
+        let empty_stacks = crate_stacks.empty_stacks();
+        if !empty_stacks.is_empty() {
+            info!("Stacks without crates on top: {:?}", empty_stacks);
         }
+        let final_top: String = crate_stacks
+            .top_crates()
+            .into_iter()
+            .map(|c| c.unwrap_or(' '))
+            .collect();
         info!("Final top crates: {}", final_top);
-        Ok(())
+        Ok(final_top)
     } else {
         Err(anyhow!("Input didn't contain any move operations!"))
     }
 }
 
-struct CargoCraneParser<'a> {
-    in_reader: BufReader<File>,
-    line: String,
-    crate_stacks: &'a mut HashMap<u32, VecDeque<char>, RandomState>,
-    task: u8,
+/// The crate stacks - stack i of the drawing is stacks[i - 1], with its bottom crate first
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stacks {
+    stacks: Vec<Vec<char>>,
 }
 
-impl<'a> CargoCraneParser<'a> {
-    fn init(
-        input: String,
-        crate_stacks: &'a mut HashMap<u32, VecDeque<char>, RandomState>,
-        task: u8,
-    ) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+impl Stacks {
+    /// parses a drawing of the stacks - the number of stacks and their columns are taken from the
+    /// numbered footer line, crate lines may be ragged or shorter than the footer
+    pub fn parse(drawing: &[Line]) -> Result<Self> {
+        let (footer, crate_lines) = drawing
+            .split_last()
+            .ok_or(anyhow!("drawing doesn't contain any lines"))?;
 
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
+        // crates are drawn above the last digit of their stack's number
+        let mut columns = Vec::new();
+        for (number, (start, label)) in words(&footer.text).enumerate() {
+            if label.parse::<usize>().ok() != Some(number + 1) {
+                bail!("stack {} is labelled '{}' in {}", number + 1, label, footer);
+            }
+            columns.push(start + label.len() - 1);
+        }
 
-        // parse the initial crate stacks
-        crate_stacks.insert(1, VecDeque::from(['H', 'R', 'B', 'D', 'Z', 'F', 'L', 'S']));
-        crate_stacks.insert(2, VecDeque::from(['T', 'B', 'M', 'Z', 'R']));
-        crate_stacks.insert(3, VecDeque::from(['Z', 'L', 'C', 'H', 'N', 'S']));
-        crate_stacks.insert(4, VecDeque::from(['S', 'C', 'F', 'J']));
-        crate_stacks.insert(5, VecDeque::from(['P', 'G', 'H', 'W', 'R', 'Z', 'B']));
-        crate_stacks.insert(6, VecDeque::from(['V', 'J', 'Z', 'G', 'D', 'N', 'M', 'T']));
-        crate_stacks.insert(7, VecDeque::from(['G', 'L', 'N', 'W', 'F', 'S', 'P', 'Q']));
-        crate_stacks.insert(8, VecDeque::from(['M', 'Z', 'R']));
-        crate_stacks.insert(9, VecDeque::from(['M', 'C', 'L', 'G', 'V', 'R', 'T']));
-
-        loop {
-            let bytes_read = in_reader
-                .read_line(&mut line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 {
-                panic!("nothing to read!");
-            } else if line == "\n" {
-                line.clear();
-                break;
+        let mut stacks = vec![Vec::new(); columns.len()];
+        for (level, line) in crate_lines.iter().rev().enumerate() {
+            let chars: Vec<char> = line.text.chars().collect();
+            for (i, &c) in chars.iter().enumerate() {
+                if !"[] ".contains(c) && !columns.contains(&i) {
+                    bail!("{} contains a crate that isn't above a stack number", line);
+                }
             }
-            line.clear();
+            for (stack, &column) in stacks.iter_mut().zip(columns.iter()) {
+                match chars.get(column) {
+                    None | Some(' ') => continue,
+                    Some(_) if stack.len() != level => {
+                        bail!("crate in {} floats above an empty position", line)
+                    }
+                    Some(&c) => stack.push(c),
+                }
+            }
+        }
+
+        Ok(Stacks { stacks })
+    }
+
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    /// crates of the stack with the given number, bottom first
+    pub fn stack(&self, number: usize) -> Option<&[char]> {
+        self.stacks
+            .get(number.checked_sub(1)?)
+            .map(|stack| stack.as_slice())
+    }
+
+    fn stack_mut(&mut self, number: usize) -> Result<&mut Vec<char>> {
+        let len = self.stacks.len();
+        number
+            .checked_sub(1)
+            .and_then(|i| self.stacks.get_mut(i))
+            .ok_or(anyhow!(
+                "crate stack {} doesn't exist, there are {}",
+                number,
+                len
+            ))
+    }
+
+    /// moves the top crates of one stack to another - the i-th crate put onto the target stack is the
+    /// order[i]-th of the lifted crates, counting from the bottom
+    fn transfer(&mut self, from: usize, to: usize, order: &[usize]) -> Result<()> {
+        self.stack_mut(to)?;
+        let stack = self.stack_mut(from)?;
+        if stack.len() < order.len() {
+            bail!(
+                "can't move {} crates, stack {} only holds {}",
+                order.len(),
+                from,
+                stack.len()
+            );
+        }
+        let lifted = stack.split_off(stack.len() - order.len());
+        self.stack_mut(to)?.extend(order.iter().map(|&i| lifted[i]));
+        Ok(())
+    }
+
+    /// top crate of every stack - None for empty stacks
+    pub fn top_crates(&self) -> Vec<Option<char>> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.last().copied())
+            .collect()
+    }
+
+    /// numbers of the stacks without any crates
+    pub fn empty_stacks(&self) -> Vec<usize> {
+        (1..=self.stacks.len())
+            .filter(|&number| self.stacks[number - 1].is_empty())
+            .collect()
+    }
+}
+
+/// whitespace-separated words of the text with their byte offsets
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// draws the stacks in the same format as the puzzle input
+impl fmt::Display for Stacks {
+    /// columns are at least 3 wide and grow with the stack numbers - the crates are drawn above
+    /// the last digit of their stack's number
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = cmp::max(3, self.stacks.len().to_string().len() + 1);
+        let height = self
+            .stacks
+            .iter()
+            .map(|stack| stack.len())
+            .max()
+            .unwrap_or(0);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("{:>width$}", format!("[{}]", c)),
+                    None => " ".repeat(width),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let footer: Vec<String> = (1..=self.stacks.len())
+            .map(|number| format!("{:>width$}", format!("{} ", number)))
+            .collect();
+        write!(f, "{}", footer.join(" "))
+    }
+}
+
+/// A single step of the rearrangement procedure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub num: usize,
+    /// stack numbers, starting at 1
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Move {
+    pub fn parse(line: &Line) -> Result<Self> {
+        let (num, from, to) = line
+            .captures(RE_MOVE)?
+            .extract(["num", "from", "to"])
+            .context("not a move operation")?;
+        Ok(Move { num, from, to })
+    }
+
+    /// moves the same number of crates back
+    pub fn inverse(&self) -> Self {
+        Move {
+            num: self.num,
+            from: self.to,
+            to: self.from,
         }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.num, self.from, self.to)
+    }
+}
+
+/// A crane model - cranes only differ in the order in which the moved crates end up on the target
+/// stack
+pub trait Crane {
+    /// for each position on the target stack (bottom first), the position of the crate that lands
+    /// there among the n lifted crates (bottom first)
+    fn order(&self, n: usize) -> Vec<usize>;
+
+    fn apply(&self, stacks: &mut Stacks, crane_move: &Move) -> Result<()> {
+        stacks.transfer(crane_move.from, crane_move.to, &self.order(crane_move.num))
+    }
+
+    /// reverts a move of this crane
+    fn undo(&self, stacks: &mut Stacks, crane_move: &Move) -> Result<()> {
+        let order = self.order(crane_move.num);
+        let mut inverse = vec![0; order.len()];
+        for (landed, &lifted) in order.iter().enumerate() {
+            inverse[lifted] = landed;
+        }
+        stacks.transfer(crane_move.to, crane_move.from, &inverse)
+    }
+}
+
+/// Moves one crate at a time, so the moved crates end up reversed
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn order(&self, n: usize) -> Vec<usize> {
+        (0..n).rev().collect()
+    }
+}
+
+/// Moves all crates at once, so they keep their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn order(&self, n: usize) -> Vec<usize> {
+        (0..n).collect()
+    }
+}
+
+/// Moves at most max crates at once - each batch keeps its order
+pub struct BatchCrane {
+    pub max: usize,
+}
+
+impl Crane for BatchCrane {
+    fn order(&self, n: usize) -> Vec<usize> {
+        let top_first: Vec<usize> = (0..n).rev().collect();
+        top_first
+            .chunks(self.max.max(1))
+            .flat_map(|batch| batch.iter().rev().copied())
+            .collect()
+    }
+}
+
+/// Applies moves one by one and remembers them, so they can be undone again
+pub struct Replay<'a> {
+    crane: &'a dyn Crane,
+    stacks: Stacks,
+    history: Vec<Move>,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(crane: &'a dyn Crane, stacks: Stacks) -> Self {
+        Replay {
+            crane,
+            stacks,
+            history: Vec::new(),
+        }
+    }
+
+    /// applies the move and returns the stacks after it
+    pub fn apply(&mut self, crane_move: Move) -> Result<&Stacks> {
+        self.crane
+            .apply(&mut self.stacks, &crane_move)
+            .context(format!("failed to apply {}", crane_move))?;
+        self.history.push(crane_move);
+        Ok(&self.stacks)
+    }
+
+    /// reverts the last applied move - returns the reverted move and the stacks before it
+    pub fn undo(&mut self) -> Result<Option<(Move, &Stacks)>> {
+        let crane_move = match self.history.pop() {
+            Some(crane_move) => crane_move,
+            None => return Ok(None),
+        };
+        self.crane
+            .undo(&mut self.stacks, &crane_move)
+            .context(format!("failed to undo {}", crane_move))?;
+        Ok(Some((crane_move, &self.stacks)))
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    /// moves applied so far
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+}
+
+struct CargoCraneParser {
+    lines: Lines<BufReader<File>>,
+    /// stacks as shown in the drawing
+    crate_stacks: Stacks,
+}
+
+impl CargoCraneParser {
+    fn init(input: String) -> Result<Self> {
+        let mut lines = input::read_lines(input)?;
+
+        // parse the initial crate stacks
+        let drawing = lines
+            .section()
+            .context("failed to read crate stack drawing")?;
+        let crate_stacks =
+            Stacks::parse(&drawing).context("failed to parse crate stack drawing")?;
 
         Ok(CargoCraneParser {
-            in_reader,
-            line,
+            lines,
             crate_stacks,
-            task,
         })
     }
 }
 
-impl<'a> Iterator for CargoCraneParser<'a> {
-    type Item = ();
+impl Iterator for CargoCraneParser {
+    type Item = Result<Move>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let bytes_read = self
-            .in_reader
-            .read_line(&mut self.line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 {
-            return None; // EOF
-        } else if self.line == "\n" {
-            return None; // No more predictions to parse
-        }
+        let line = match self.lines.next()? {
+            Ok(line) if line.is_blank() => return None, // No more moves to parse
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+
+        debug!("parsing {}", line);
+
+        Some(Move::parse(&line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::{
+        day_5::{BatchCrane, Crane, CrateMover9000, CrateMover9001, Move, Replay, Stacks},
+        util::input::Lines,
+    };
+
+    fn parse(drawing: &str) -> anyhow::Result<Stacks> {
+        let lines: Vec<_> = Lines::new(drawing.as_bytes()).map(|l| l.unwrap()).collect();
+        Stacks::parse(&lines)
+    }
+
+    #[test]
+    fn drawing_test() {
+        // ragged lines without trailing spaces, and an empty fourth stack
+        let stacks = parse("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3   4").unwrap();
+        assert_eq!(stacks.len(), 4);
+        assert_eq!(stacks.stack(1), Some(&['Z', 'N'][..]));
+        assert_eq!(
+            stacks.top_crates(),
+            vec![Some('N'), Some('D'), Some('P'), None]
+        );
+        assert_eq!(stacks.empty_stacks(), vec![4]);
+        assert_eq!(
+            stacks.to_string(),
+            "    [D]        \n[N] [C]        \n[Z] [M] [P]    \n 1   2   3   4 "
+        );
+        assert_eq!(parse(&stacks.to_string()).unwrap(), stacks);
+
+        let many: String = (1..=12).map(|n| format!("{:^3} ", n)).collect();
+        assert_eq!(
+            parse(&format!("{}[A]\n{}", " ".repeat(44), many))
+                .unwrap()
+                .stack(12),
+            Some(&['A'][..])
+        );
+
+        let mut wide = Stacks {
+            stacks: vec![Vec::new(); 120],
+        };
+        wide.stacks[99].push('X');
+        wide.stacks[119].extend(['Y', 'Z']);
+        let drawing = wide.to_string();
+        assert!(drawing.ends_with(" 119  120 "));
+        assert_eq!(parse(&drawing).unwrap(), wide);
+
+        assert!(parse("[A]\n 1   3").is_err());
+        assert!(parse("[A]\n    [B]\n 1   2").is_err());
+        assert!(parse("        [A]\n 1   2").is_err());
+    }
 
-        debug!("parsing line {}", self.line);
-
-        // Apply move operation
-        let re = Regex::new(r"move (\d+) from (\d) to (\d)").unwrap();
-        let caps = re.captures(self.line.as_str()).unwrap();
-
-        let from_stack = caps.get(2).unwrap().as_str().parse::<u32>().unwrap();
-        let to_stack = caps.get(3).unwrap().as_str().parse::<u32>().unwrap();
-        let mut move_stack = VecDeque::new();
-        for _ in 0..caps.get(1).unwrap().as_str().parse::<u32>().unwrap() {
-            let cargo_box = self
-                .crate_stacks
-                .get_mut(&from_stack)
-                .expect(format!("crate stack {} is missing!", from_stack).as_str())
-                .pop_back()
-                .expect(format!("crate stack {} is empty!", from_stack).as_str());
-            match self.task {
-                1 => move_stack.push_back(cargo_box),
-                2 => move_stack.push_front(cargo_box),
-                _ => panic!("task doesn't exist!"),
+    #[test]
+    fn crane_test() {
+        let drawing = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ";
+        let stacks = parse(drawing).unwrap();
+        let moves = [
+            Move {
+                num: 1,
+                from: 2,
+                to: 1,
+            },
+            Move {
+                num: 3,
+                from: 1,
+                to: 3,
+            },
+            Move {
+                num: 2,
+                from: 2,
+                to: 1,
+            },
+            Move {
+                num: 1,
+                from: 1,
+                to: 2,
+            },
+        ];
+        let cranes: [(&dyn Crane, &str); 4] = [
+            (&CrateMover9000, "CMZ"),
+            (&CrateMover9001, "MCD"),
+            (&BatchCrane { max: 1 }, "CMZ"),
+            (&BatchCrane { max: 2 }, "MCZ"),
+        ];
+        for (crane, expected) in cranes {
+            let mut replay = Replay::new(crane, stacks.clone());
+            for crane_move in moves {
+                replay.apply(crane_move).unwrap();
             }
+            let tops: String = replay.stacks().top_crates().into_iter().flatten().collect();
+            assert_eq!(tops, expected);
+            while replay.undo().unwrap().is_some() {}
+            assert_eq!(replay.stacks(), &stacks);
         }
-        self.crate_stacks
-            .get_mut(&to_stack)
-            .expect(format!("crate stack {} is missing!", to_stack).as_str())
-            .append(&mut move_stack);
+        assert_eq!(BatchCrane { max: 2 }.order(5), vec![3, 4, 1, 2, 0]);
 
-        self.line.clear();
-        Some(())
+        // failed moves leave the stacks untouched
+        let mut replay = Replay::new(&CrateMover9000, stacks.clone());
+        assert!(replay
+            .apply(Move {
+                num: 4,
+                from: 1,
+                to: 2
+            })
+            .is_err());
+        assert!(replay
+            .apply(Move {
+                num: 1,
+                from: 1,
+                to: 4
+            })
+            .is_err());
+        assert_eq!(replay.stacks(), &stacks);
+        assert!(replay.history().is_empty());
     }
 }
@@ -1,82 +1,201 @@
-use anyhow::{anyhow, Context, Result};
-use itertools::Itertools;
-use log::info;
 use std::{
     collections::VecDeque,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, Read},
 };
 
-pub fn solve(task: u8, input: String) -> Result<()> {
-    // instantiate parser
-    let parser = DataStreamParser::init(input).context("Failed to instantiate parser")?;
-
-    let mut in_buff = VecDeque::new();
-
-    for (c, i) in parser {
-        if in_buff.len()
-            == (match task {
-                1 => 4,
-                2 => 14,
-                _ => panic!("task doesn't exist!"),
-            })
-        {
-            let _ = in_buff.pop_front().unwrap();
-            in_buff.push_back(c);
-            if in_buff.iter().unique().count() == in_buff.len() {
-                info!(
-                    "first marker after character {} for marker {:?}",
-                    i + 1,
-                    in_buff
-                );
-                break;
-            }
-        } else {
-            in_buff.push_back(c);
-        }
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, log_enabled, Level};
+
+/// number of bytes read from the stream at once
+const CHUNK_SIZE: usize = 1 << 16;
+
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let window = match task {
+        1 => 4,
+        2 => 14,
+        _ => bail!("task doesn't exist!"),
+    };
+
+    let mut markers = Markers::new(open_stream(&input)?, window)?;
+    let marker = markers
+        .next()
+        .ok_or(anyhow!("data stream didn't contain a marker"))??;
+    if log_enabled!(Level::Debug) {
+        debug!("data stream contains {} markers", markers.count() + 1);
     }
+    info!("first marker after character {}", marker);
 
-    Ok(())
+    Ok(marker.to_string())
 }
 
-struct DataStreamParser {
-    char_list: Vec<char>,
-    char_index: u32,
+/// opens the input file - "-" reads from stdin instead
+pub fn open_stream(input: &str) -> Result<Box<dyn Read>> {
+    if input == "-" {
+        return Ok(Box::new(io::stdin()));
+    }
+    let file = File::open(input).context(format!("Failed to read input {}", input))?;
+    Ok(Box::new(file))
+}
+
+/// Tracks the last `window` characters and how often each of them occurs
+pub struct MarkerDetector {
+    window: usize,
+    buffer: VecDeque<u8>,
+    counts: [usize; 256],
+    /// number of different characters in the buffer
+    distinct: usize,
 }
 
-impl DataStreamParser {
-    fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-        let bytes_read = in_reader
-            .read_line(&mut line)
-            .expect("failed to read input file");
-        if bytes_read == 0 {
-            return Err(anyhow!("input file was empty!"));
+impl MarkerDetector {
+    pub fn new(window: usize) -> Result<Self> {
+        if window == 0 {
+            bail!("markers have to be at least one character long");
         }
+        Ok(MarkerDetector {
+            window,
+            buffer: VecDeque::with_capacity(window + 1),
+            counts: [0; 256],
+            distinct: 0,
+        })
+    }
 
-        Ok(DataStreamParser {
-            char_list: line.chars().collect(),
-            char_index: 0,
+    /// adds the next character - returns true if the last `window` characters are all different
+    pub fn push(&mut self, c: u8) -> bool {
+        self.buffer.push_back(c);
+        self.counts[c as usize] += 1;
+        if self.counts[c as usize] == 1 {
+            self.distinct += 1;
+        }
+        if self.buffer.len() > self.window {
+            let old = self.buffer.pop_front().expect("buffer can't be empty");
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.distinct == self.window
+    }
+}
+
+/// Iterates over the positions of all markers in a stream - a position is the number of characters
+/// read up to and including the marker's last one, line breaks aren't counted
+pub struct Markers<R> {
+    reader: R,
+    detector: MarkerDetector,
+    chunk: Vec<u8>,
+    /// next unprocessed byte of the chunk
+    offset: usize,
+    /// number of characters processed
+    position: usize,
+}
+
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R, window: usize) -> Result<Self> {
+        Ok(Markers {
+            reader,
+            detector: MarkerDetector::new(window)?,
+            chunk: Vec::new(),
+            offset: 0,
+            position: 0,
         })
     }
 }
 
-impl Iterator for DataStreamParser {
-    type Item = (char, u32);
+impl<R: Read> Iterator for Markers<R> {
+    type Item = Result<usize>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next_char = self.char_list.get(self.char_index as usize);
-        if let Some(c) = next_char {
-            let i = self.char_index;
-            self.char_index += 1;
-            Some((c.to_owned(), i))
-        } else {
-            None
+        loop {
+            if self.offset == self.chunk.len() {
+                self.chunk.resize(CHUNK_SIZE, 0);
+                self.offset = 0;
+                let read = loop {
+                    match self.reader.read(&mut self.chunk) {
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        read => break read,
+                    }
+                };
+                match read {
+                    Ok(n) => self.chunk.truncate(n),
+                    Err(e) => {
+                        self.chunk.clear();
+                        return Some(Err(e).context(format!(
+                            "Failed to read data stream after character {}",
+                            self.position
+                        )));
+                    }
+                }
+                if self.chunk.is_empty() {
+                    return None; // EOF
+                }
+            }
+
+            let c = self.chunk[self.offset];
+            self.offset += 1;
+            if c == b'\n' || c == b'\r' {
+                continue;
+            }
+            self.position += 1;
+            if self.detector.push(c) {
+                return Some(Ok(self.position));
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use crate::task_solver::day_6::Markers;
+
+    /// hands out the stream in small reads, and is interrupted before every second one
+    struct Interrupting<'a> {
+        stream: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for Interrupting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            (&mut self.stream).take(3).read(buf)
+        }
+    }
+
+    fn markers(stream: impl Read, window: usize) -> Vec<usize> {
+        Markers::new(stream, window)
+            .unwrap()
+            .map(|m| m.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn markers_test() {
+        let stream = "bvwbjplbgvbhsrlpgdmjqwftvncz\n";
+        assert_eq!(markers(stream.as_bytes(), 4).first(), Some(&5));
+        assert_eq!(markers(stream.as_bytes(), 14).first(), Some(&23));
+        assert!(markers("abab".as_bytes(), 3).is_empty());
+        assert_eq!(markers("aab\ncd".as_bytes(), 2), vec![3, 4, 5]);
+        assert_eq!(markers("aa".as_bytes(), 1), vec![1, 2]);
+        assert!(Markers::new("".as_bytes(), 0).is_err());
+
+        // markers spanning chunk boundaries
+        let long: Vec<u8> = (0..200_000u32).map(|i| b"aab"[i as usize % 3]).collect();
+        let found = markers(long.as_slice(), 2);
+        let expected: Vec<usize> = (2..=200_000).filter(|p| p % 3 != 2).collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn interrupted_reads() {
+        let stream = Interrupting {
+            stream: "aab\ncdd".as_bytes(),
+            interrupt: false,
+        };
+        assert_eq!(markers(stream, 2), vec![3, 4, 5]);
+    }
+}
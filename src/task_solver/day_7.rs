@@ -1,148 +1,552 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, info};
-use regex::Regex;
+use serde_json::{json, Value};
 use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{BufRead, BufReader},
-    u64,
+    collections::BTreeMap,
+    fs::{self, File},
+    path::Path,
 };
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
-    let root_dir =
-        Directory::init_from_input(input).context("failed to instantiate file system")?;
+use super::util::input::{self, Line};
+
+const RE_CD: &str = r"^\$ cd (?P<dir_name>.+)$";
+const RE_LS_FILE: &str = r"^(?P<file_size>\d+) (?P<file_name>.+)$";
+
+const DISK_SIZE: u64 = 70000000;
+const UPDATE_SIZE: u64 = 30000000;
+
+pub fn solve(_task: u8, input: String) -> Result<String> {
+    // the input is either a transcript or a real directory
+    let root_dir = if Path::new(&input).is_dir() {
+        Directory::from_disk(Path::new(&input))
+    } else {
+        Directory::init_from_input(input)
+    }
+    .context("failed to instantiate file system")?;
     debug!("size of root dir: {}", root_dir.file_size);
 
-    match _task {
-        1 => info!(
-            "sum of all total dir sizes of at most 100000: {}",
-            root_dir.solve_1()
-        ),
-        2 => info!(
-            "size of smallest possible dir that could free up enough space: {}",
-            root_dir.solve_2(30000000 - (70000000 - root_dir.file_size))
-        ),
+    let size = match _task {
+        1 => {
+            let size = root_dir.solve_1();
+            info!("sum of all total dir sizes of at most 100000: {}", size);
+            size
+        }
+        2 => {
+            let size = root_dir.solve_2(root_dir.space_to_free()?);
+            info!(
+                "size of smallest possible dir that could free up enough space: {}",
+                size
+            );
+            size
+        }
         _ => return Err(anyhow!("task doesn't exist!")),
-    }
-    Ok(())
+    };
+    Ok(size.to_string())
 }
 
-struct Directory {
-    name: String,
-    sub_dirs: Vec<Directory>,
-    file_size: u64, // size of files contained at this dir level
+/// A directory of the file system, with everything below it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Directory {
+    /// absolute path - "/" for the root, "/a/e" for its subdir e of subdir a
+    pub path: String,
+    pub name: String,
+    /// sizes of the files directly in this dir, by name
+    pub files: BTreeMap<String, u64>,
+    pub sub_dirs: BTreeMap<String, Directory>,
+    /// total size of all files in this dir and below
+    pub file_size: u64,
 }
 
 impl Directory {
-    fn init_from_input(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let mut in_reader = BufReader::new(in_file);
-        let mut line = String::new();
-        let mut dir_stack: VecDeque<Directory> = VecDeque::new(); // cd dir path
-
-        let mut curr_dir = Directory {
-            name: "stub".to_owned(),
-            sub_dirs: vec![],
-            file_size: 0u64,
+    fn new(parent: Option<&str>, name: &str) -> Self {
+        let path = match parent {
+            None => "/".to_owned(),
+            Some("/") => format!("/{}", name),
+            Some(parent) => format!("{}/{}", parent, name),
         };
+        Directory {
+            path,
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    pub fn root() -> Self {
+        Directory::new(None, "/")
+    }
+
+    pub fn init_from_input(input: String) -> Result<Self> {
+        let mut transcript = Transcript::default();
+        for line in input::read_lines(input)? {
+            let line = line?;
+            if line.is_blank() {
+                continue;
+            }
+            transcript
+                .feed(&line)
+                .context(format!("failed to process {}", line))?;
+        }
+        Ok(transcript.finish())
+    }
+
+    /// builds the tree of an existing directory - symlinks aren't followed but skipped
+    pub fn from_disk(path: &Path) -> Result<Self> {
+        let mut root = Directory::root();
+        root.read_dir(path)?;
+        root.update_sizes();
+        Ok(root)
+    }
+
+    fn read_dir(&mut self, path: &Path) -> Result<()> {
+        for entry in fs::read_dir(path).context(format!("failed to read dir {:?}", path))? {
+            let entry = entry.context(format!("failed to read entry of {:?}", path))?;
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|name| anyhow!("{:?} isn't valid UTF-8", name))?;
+            let metadata = entry
+                .metadata()
+                .context(format!("failed to read metadata of {:?}", entry.path()))?;
+            if metadata.is_dir() {
+                let mut sub_dir = Directory::new(Some(&self.path), &name);
+                sub_dir.read_dir(&entry.path())?;
+                self.sub_dirs.insert(name, sub_dir);
+            } else if metadata.is_file() {
+                self.files.insert(name, metadata.len());
+            } else {
+                debug!("skipping {:?}", entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    /// creates the dirs and files of the tree in the target dir - files are sparse where the file
+    /// system supports it, so large sizes don't use up actual disk space
+    pub fn write_to(&self, target: &Path) -> Result<()> {
+        fs::create_dir_all(target).context(format!("failed to create dir {:?}", target))?;
+        for (name, &size) in self.files.iter() {
+            let file_path = target.join(check_name(name)?);
+            File::create(&file_path)
+                .and_then(|file| file.set_len(size))
+                .context(format!("failed to write file {:?}", file_path))?;
+        }
+        for (name, sub_dir) in self.sub_dirs.iter() {
+            sub_dir.write_to(&target.join(check_name(name)?))?;
+        }
+        Ok(())
+    }
+
+    /// the `$ cd`/`$ ls` commands and output that explore this dir, starting from the root
+    pub fn transcript(&self) -> String {
+        // one cd per path component, since names can't contain slashes
+        let mut lines = vec!["$ cd /".to_owned()];
+        lines.extend(
+            self.path
+                .split('/')
+                .filter(|name| !name.is_empty())
+                .map(|name| format!("$ cd {}", name)),
+        );
+        self.transcript_lines(&mut lines);
+        lines.join("\n")
+    }
 
-        // command regex
-        let re_cd = Regex::new(r"^\$ cd (?P<dir_name>.*)").unwrap();
-        let re_ls = Regex::new(r"^\$ ls").unwrap();
-        let re_ls_dir = Regex::new(r"^dir (?P<dir_name>.*)").unwrap();
-        let re_ls_file = Regex::new(r"^(?P<file_size>\d+) (?P<file_name>.*)").unwrap();
-
-        loop {
-            let bytes_read = in_reader
-                .read_line(&mut line)
-                .expect("Failed to read line in input file");
-            if bytes_read == 0 || line == "\n" {
-                // unwind until we reach root
-                while curr_dir.name != "/" {
-                    let mut prev_dir = dir_stack.pop_front().context("dir stack is empty!")?;
-                    prev_dir.file_size += curr_dir.file_size;
-                    prev_dir.sub_dirs.push(curr_dir);
-                    curr_dir = prev_dir;
+    fn transcript_lines(&self, lines: &mut Vec<String>) {
+        lines.push("$ ls".to_owned());
+        lines.extend(self.sub_dirs.keys().map(|name| format!("dir {}", name)));
+        lines.extend(
+            self.files
+                .iter()
+                .map(|(name, size)| format!("{} {}", size, name)),
+        );
+        for (name, sub_dir) in self.sub_dirs.iter() {
+            lines.push(format!("$ cd {}", name));
+            sub_dir.transcript_lines(lines);
+            lines.push("$ cd ..".to_owned());
+        }
+    }
+
+    /// the file or directory at the absolute path
+    pub fn get(&self, path: &str) -> Option<Entry<'_>> {
+        let mut dir = self;
+        let mut names = path
+            .strip_prefix('/')?
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .peekable();
+        while let Some(name) = names.next() {
+            match (dir.sub_dirs.get(name), dir.files.get(name)) {
+                (Some(sub_dir), _) => dir = sub_dir,
+                (None, Some(&size)) if names.peek().is_none() => {
+                    return Some(Entry::File {
+                        path: dir.child_path(name),
+                        size,
+                    })
                 }
-                return Ok(curr_dir); // EOF
+                _ => return None,
             }
+        }
+        Some(Entry::Dir(dir))
+    }
+
+    /// total size of the file or directory at the absolute path
+    pub fn du(&self, path: &str) -> Result<u64> {
+        self.get(path)
+            .map(|entry| entry.size())
+            .ok_or(anyhow!("{} doesn't exist", path))
+    }
 
-            if re_cd.is_match(&line) {
-                let cd_captures = re_cd
-                    .captures(&line)
-                    .context("cd regex failed to capture line")?;
-                let dir_name = cd_captures
-                    .name("dir_name")
-                    .context("cd regex didn't contain expected named capture group")?
-                    .as_str();
-                if dir_name == ".." {
-                    let mut prev_dir = dir_stack.pop_front().context("dir stack is empty!")?;
-                    prev_dir.file_size += curr_dir.file_size;
-                    debug!(
-                        "final file size of {}: {}",
-                        curr_dir.name, curr_dir.file_size
-                    );
-                    prev_dir.sub_dirs.push(curr_dir);
-                    curr_dir = prev_dir;
-                } else {
-                    let cd_dir = Directory {
-                        name: dir_name.to_owned(),
-                        sub_dirs: vec![],
-                        file_size: 0,
-                    };
-                    dir_stack.push_front(curr_dir);
-                    curr_dir = cd_dir;
+    /// this dir and all dirs below it, parents before their children
+    pub fn dirs(&self) -> Vec<&Directory> {
+        let mut dirs = vec![self];
+        for sub_dir in self.sub_dirs.values() {
+            dirs.extend(sub_dir.dirs());
+        }
+        dirs
+    }
+
+    /// all files and dirs below this one (including itself) that satisfy the predicate, e.g.
+    /// `|entry| entry.size() <= 100000`
+    pub fn find(&self, predicate: impl Fn(&Entry) -> bool) -> Vec<Entry<'_>> {
+        let mut found = Vec::new();
+        for dir in self.dirs() {
+            let dir_entry = Entry::Dir(dir);
+            if predicate(&dir_entry) {
+                found.push(dir_entry);
+            }
+            for (name, &size) in dir.files.iter() {
+                let file_entry = Entry::File {
+                    path: dir.child_path(name),
+                    size,
+                };
+                if predicate(&file_entry) {
+                    found.push(file_entry);
                 }
-                debug!("cmd cd - new curr dir: {}", dir_name);
-            } else if re_ls.is_match(&line) {
-                curr_dir.file_size = 0u64;
-                debug!("cmd ls - curr dir: {}", curr_dir.name);
-            } else if re_ls_dir.is_match(&line) {
-                debug!("ls dir: {}", line);
-            } else if re_ls_file.is_match(&line) {
-                let ls_captures = re_ls_file
-                    .captures(&line)
-                    .context("ls_file regex failed to capture line")?;
-                let file_size = ls_captures
-                    .name("file_size")
-                    .context("ls_file regex didn't contain expected named capture group")?
-                    .as_str()
-                    .parse::<u64>()
-                    .context("failed to parse matched file size as u64")?;
-                curr_dir.file_size += file_size;
-                debug!("ls file: {}", line);
+            }
+        }
+        found
+    }
+
+    /// draws the tree like the `tree` command does, with sizes in parentheses
+    pub fn tree(&self) -> String {
+        let mut lines = vec![format!("{} (dir, size={})", self.name, self.file_size)];
+        self.tree_lines("", &mut lines);
+        lines.join("\n")
+    }
+
+    fn tree_lines(&self, prefix: &str, lines: &mut Vec<String>) {
+        let entries: Vec<_> = self
+            .sub_dirs
+            .values()
+            .map(|dir| (&dir.name, Some(dir), dir.file_size))
+            .chain(self.files.iter().map(|(name, &size)| (name, None, size)))
+            .collect();
+        for (i, (name, dir, size)) in entries.iter().enumerate() {
+            let is_last = i + 1 == entries.len();
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
             } else {
-                return Err(anyhow!("unknown terminal output: {}", line));
+                ("├── ", "│   ")
+            };
+            match dir {
+                Some(dir) => {
+                    lines.push(format!("{}{}{} (dir, size={})", prefix, branch, name, size));
+                    dir.tree_lines(&format!("{}{}", prefix, indent), lines);
+                }
+                None => lines.push(format!(
+                    "{}{}{} (file, size={})",
+                    prefix, branch, name, size
+                )),
             }
-            line.clear();
         }
     }
 
-    fn solve_1(&self) -> u64 {
-        let mut total_size = 0u64;
-        for subdir in &self.sub_dirs {
-            total_size += subdir.solve_1();
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "path": self.path,
+            "size": self.file_size,
+            "files": self
+                .files
+                .iter()
+                .map(|(name, size)| json!({ "name": name, "path": self.child_path(name), "size": size }))
+                .collect::<Vec<_>>(),
+            "dirs": self.sub_dirs.values().map(Directory::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    fn child_path(&self, name: &str) -> String {
+        Directory::new(Some(&self.path), name).path
+    }
+
+    /// recomputes the total sizes below this dir
+    fn update_sizes(&mut self) -> u64 {
+        self.file_size = self.files.values().sum::<u64>()
+            + self
+                .sub_dirs
+                .values_mut()
+                .map(|dir| dir.update_sizes())
+                .sum::<u64>();
+        self.file_size
+    }
+
+    pub fn solve_1(&self) -> u64 {
+        self.dirs()
+            .into_iter()
+            .map(|dir| dir.file_size)
+            .filter(|&size| size <= 100000)
+            .sum()
+    }
+
+    /// how much space has to be freed for the update - errors if the files don't even fit onto
+    /// the disk
+    pub fn space_to_free(&self) -> Result<u64> {
+        let free = DISK_SIZE.checked_sub(self.file_size).ok_or(anyhow!(
+            "{} uses {} bytes, but the disk only holds {}",
+            self.path,
+            self.file_size,
+            DISK_SIZE
+        ))?;
+        Ok(UPDATE_SIZE.saturating_sub(free))
+    }
+
+    /// size of the smallest dir that frees up the required space - 0 if nothing needs to be deleted
+    pub fn solve_2(&self, required_space: u64) -> u64 {
+        if required_space == 0 {
+            return 0;
+        }
+        self.dirs()
+            .into_iter()
+            .map(|dir| dir.file_size)
+            .filter(|&size| size >= required_space)
+            .min()
+            .unwrap_or(self.file_size)
+    }
+}
+
+/// makes sure that a name from a transcript can't refer to anything outside of its dir
+fn check_name(name: &str) -> Result<&str> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        bail!("'{}' can't be used as a file name", name);
+    }
+    Ok(name)
+}
+
+/// A file or directory of the file system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Entry<'a> {
+    File { path: String, size: u64 },
+    Dir(&'a Directory),
+}
+
+impl Entry<'_> {
+    pub fn path(&self) -> &str {
+        match self {
+            Entry::File { path, .. } => path,
+            Entry::Dir(dir) => &dir.path,
+        }
+    }
+
+    /// total size of the entry - for directories, including everything below it
+    pub fn size(&self) -> u64 {
+        match self {
+            Entry::File { size, .. } => *size,
+            Entry::Dir(dir) => dir.file_size,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Entry::Dir(_))
+    }
+}
+
+/// Replays a terminal transcript of `cd` and `ls` commands into a directory tree
+struct Transcript {
+    root: Directory,
+    /// names of the dirs from the root to the current dir
+    cwd: Vec<String>,
+    /// whether the following lines are output of `ls`
+    listing: bool,
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Transcript {
+            root: Directory::root(),
+            cwd: Vec::new(),
+            listing: false,
         }
-        debug!("{} has size {}", self.name, self.file_size);
-        if self.file_size <= 100000 {
-            total_size += self.file_size;
+    }
+}
+
+impl Transcript {
+    fn cwd_mut(&mut self) -> &mut Directory {
+        let mut dir = &mut self.root;
+        for name in self.cwd.iter() {
+            dir = dir.sub_dirs.get_mut(name).expect("cwd has to exist");
         }
-        total_size
+        dir
     }
 
-    fn solve_2(&self, required_space: u64) -> u64 {
-        let mut curr_choice = self.file_size;
-        for subdir in &self.sub_dirs {
-            if subdir.file_size > required_space {
-                let subdir_choice = subdir.solve_2(required_space);
-                if curr_choice >= subdir_choice {
-                    curr_choice = subdir_choice;
+    fn feed(&mut self, line: &Line) -> Result<()> {
+        let text = line.text.trim_end();
+
+        if let Some(command) = text.strip_prefix("$ ") {
+            self.listing = false;
+            match command.split_whitespace().next() {
+                Some("ls") if command.trim() == "ls" => self.listing = true,
+                Some("ls") => bail!("ls doesn't take any arguments"),
+                Some("cd") => {
+                    let dir_name: String = line.captures(RE_CD)?.get("dir_name")?;
+                    match dir_name.as_str() {
+                        "/" => self.cwd.clear(),
+                        ".." => {
+                            self.cwd.pop().ok_or(anyhow!("can't leave the root dir"))?;
+                        }
+                        name => {
+                            let cwd = self.cwd_mut();
+                            if cwd.files.contains_key(name) {
+                                bail!("{} is a file, not a dir", cwd.child_path(name));
+                            }
+                            if !cwd.sub_dirs.contains_key(name) {
+                                // entering a dir before listing its parent
+                                let sub_dir = Directory::new(Some(&cwd.path), name);
+                                cwd.sub_dirs.insert(name.to_owned(), sub_dir);
+                            }
+                            self.cwd.push(name.to_owned());
+                        }
+                    }
+                    debug!("cmd cd - new curr dir: {}", self.cwd_mut().path);
                 }
+                _ => bail!("unknown command '{}'", command),
             }
+            return Ok(());
         }
-        curr_choice
+
+        if !self.listing {
+            bail!("output without a preceding ls");
+        }
+        let cwd = self.cwd_mut();
+        if let Some(name) = text.strip_prefix("dir ") {
+            if cwd.files.contains_key(name) {
+                bail!("{} is listed as a file and as a dir", cwd.child_path(name));
+            }
+            if !cwd.sub_dirs.contains_key(name) {
+                let sub_dir = Directory::new(Some(&cwd.path), name);
+                cwd.sub_dirs.insert(name.to_owned(), sub_dir);
+            }
+        } else {
+            let captures = line.captures(RE_LS_FILE)?;
+            let (size, name): (u64, String) = captures.extract(["file_size", "file_name"])?;
+            if cwd.sub_dirs.contains_key(&name) {
+                bail!("{} is listed as a file and as a dir", cwd.child_path(&name));
+            }
+            // listing a dir again overwrites sizes of files that changed since
+            cwd.files.insert(name, size);
+        }
+        debug!("ls: {}", line);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Directory {
+        self.root.update_sizes();
+        self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::{
+        day_7::{Directory, Entry, Transcript},
+        util::input::Lines,
+    };
+
+    const TRANSCRIPT: &str = "$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst\n$ cd e\n$ ls\n584 i\n$ cd /\n$ cd d\n$ ls\n4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d";
+
+    fn replay(transcript: &str) -> anyhow::Result<super::Directory> {
+        let mut replay = Transcript::default();
+        for line in Lines::new(transcript.as_bytes()) {
+            replay.feed(&line?)?;
+        }
+        Ok(replay.finish())
+    }
+
+    #[test]
+    fn transcript_test() {
+        let root = replay(TRANSCRIPT).unwrap();
+        assert_eq!(root.du("/").unwrap(), 48381165);
+        assert_eq!(root.du("/a").unwrap(), 94853);
+        assert_eq!(root.du("/a/e/i").unwrap(), 584);
+        assert!(root.du("/a/x").is_err());
+        assert_eq!(root.get("/a/e").unwrap().path(), "/a/e");
+        assert_eq!((root.solve_1(), root.solve_2(8381165)), (95437, 24933642));
+
+        let small: Vec<_> = root
+            .find(|e| e.size() < 3000)
+            .iter()
+            .map(|e| (e.path().to_owned(), e.is_dir()))
+            .collect();
+        assert_eq!(
+            small,
+            vec![
+                ("/a/g".to_owned(), false),
+                ("/a/e".to_owned(), true),
+                ("/a/e/i".to_owned(), false)
+            ]
+        );
+
+        let tree = root.tree();
+        assert!(tree.starts_with("/ (dir, size=48381165)\n├── a (dir, size=94853)\n│   ├── e (dir, size=584)\n│   │   └── i (file, size=584)\n"));
+        assert!(tree.ends_with("└── c.dat (file, size=8504156)"));
+
+        let json = root.to_json();
+        assert_eq!(json["dirs"][0]["dirs"][0]["files"][0]["path"], "/a/e/i");
+        assert_eq!(json["size"], 48381165);
+    }
+
+    #[test]
+    fn transcript_error_test() {
+        assert!(replay("$ cd /\n$ cd ..").is_err());
+        assert!(replay("$ cd /\n123 a").is_err());
+        assert!(replay("$ cd /\n$ rm -rf a").is_err());
+        assert!(replay("$ ls\n1 a\ndir a").is_err());
+        assert!(replay("$ ls\n1 a\n$ cd a").is_err());
+    }
+
+    #[test]
+    fn disk_round_trip_test() {
+        let root = replay(TRANSCRIPT).unwrap();
+        let target = tempfile::tempdir().unwrap();
+        root.write_to(target.path()).unwrap();
+
+        let from_disk = Directory::from_disk(target.path()).unwrap();
+        assert_eq!(from_disk, root);
+        assert_eq!(from_disk.solve_1(), 95437);
+        assert_eq!(replay(&from_disk.transcript()).unwrap(), root);
+
+        // replaying a subdir's transcript rebuilds the root with only that subdir filled in
+        let Some(Entry::Dir(e)) = root.get("/a/e") else {
+            panic!("/a/e is a dir");
+        };
+        let replayed = replay(&e.transcript()).unwrap();
+        assert!(e.transcript().starts_with("$ cd /\n$ cd a\n$ cd e\n$ ls"));
+        assert_eq!(replayed.get("/a/e"), Some(Entry::Dir(e)));
+
+        let mut evil = replay("$ ls\ndir a").unwrap();
+        evil.files.insert("..".to_owned(), 1);
+        assert!(evil.write_to(target.path()).is_err());
+    }
+
+    #[test]
+    fn disk_space_test() {
+        let solve_2 = |transcript: &str| {
+            let target = tempfile::tempdir().unwrap();
+            replay(transcript).unwrap().write_to(target.path()).unwrap();
+            super::solve(2, target.path().to_str().unwrap().to_owned())
+        };
+        // 20000000 are free, so another 10000000 are needed
+        let answer = solve_2("$ ls\n40000000 a\ndir b\n$ cd b\n$ ls\n10000000 c");
+        assert_eq!(answer.unwrap(), "10000000");
+        assert_eq!(solve_2("$ ls\n100 a").unwrap(), "0");
+        assert!(solve_2("$ ls\n80000000 a").is_err());
     }
 }
@@ -1,243 +1,238 @@
 use anyhow::{anyhow, Context, Result};
-use core::panic;
 use log::{debug, info};
-use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{collections::VecDeque, fs::File, io::BufReader};
 
-pub fn solve(_task: u8, input: String) -> Result<()> {
-    // instantiate parser
-    let parser = TreeParser::init(input).context("Failed to instantiate parser")?;
+use super::util::input::{self, Lines};
+
+pub fn solve(_task: u8, input: String) -> Result<String> {
+    let forest = Forest::init(input).context("Failed to instantiate forest")?;
+    debug!(
+        "{}x{} forest - best spots: {:?}",
+        forest.width(),
+        forest.height(),
+        forest.top_spots(3)
+    );
 
     match _task {
-        1 => solve_1(parser),
-        2 => solve_2(parser),
+        1 => solve_1(&forest),
+        2 => solve_2(&forest),
         _ => Err(anyhow!("task doesn't exist!")),
     }
 }
 
-fn solve_1(parser: TreeParser) -> Result<()> {
-    let mut tree_grid = Vec::new();
-    let mut row_viewpoints = Vec::new();
-    let mut col_viewpoints = Vec::new();
-    for (x, y, tree_height) in parser {
-        debug!("tree at ({},{}) has height {}", x, y, tree_height);
-        if row_viewpoints.len() < (x + 1) as usize {
-            tree_grid.push(Vec::new());
-            row_viewpoints.push((Viewpoint::init(true), Viewpoint::init(false)));
-        }
-        tree_grid[x as usize].push(tree_height);
-        if col_viewpoints.len() < (y + 1) as usize {
-            col_viewpoints.push((Viewpoint::init(true), Viewpoint::init(false)));
+fn solve_1(forest: &Forest) -> Result<String> {
+    let visible = forest.sweep().visible.iter().filter(|&&v| v).count();
+
+    info!("number of visible trees: {}", visible);
+
+    Ok(visible.to_string())
+}
+
+fn solve_2(forest: &Forest) -> Result<String> {
+    let max_score = forest.sweep().scores.into_iter().max().unwrap_or(0);
+
+    info!("top score: {}", max_score);
+
+    Ok(max_score.to_string())
+}
+
+/// Visibility and scenic score of every tree, row by row
+pub struct Sweep {
+    pub visible: Vec<bool>,
+    pub scores: Vec<u64>,
+}
+
+/// Tree heights of a rectangular forest, stored row by row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Forest {
+    width: usize,
+    height: usize,
+    trees: Vec<u8>,
+}
+
+/// characters of the ASCII heatmap, from lowest to highest value
+const HEATMAP_RAMP: &[u8] = b" .:-=+*#%@";
+
+impl Forest {
+    pub fn init(input: String) -> Result<Self> {
+        let parser = TreeParser::init(input).context("Failed to instantiate parser")?;
+        let mut rows: Vec<Vec<u8>> = Vec::new();
+        for tree in parser {
+            let (x, _, tree_height) = tree?;
+            if rows.len() <= x as usize {
+                rows.push(Vec::new());
+            }
+            rows[x as usize].push(tree_height as u8);
         }
-        row_viewpoints[x as usize].0.add_tree(y, tree_height);
-        row_viewpoints[x as usize].1.add_tree(98 - y, tree_height);
-        col_viewpoints[y as usize].0.add_tree(x, tree_height);
-        col_viewpoints[y as usize].1.add_tree(98 - x, tree_height);
+        Forest::from_rows(rows)
     }
 
-    let mut visible = 0u32;
-    for (x, tree_row) in tree_grid.iter().enumerate() {
-        let (row_start, row_end) = &row_viewpoints[x];
-        for (y, tree_height) in tree_row.iter().enumerate() {
-            let (col_start, col_end) = &col_viewpoints[y];
-            if row_start.sees_tree(y, tree_height)
-                || row_end.sees_tree(y, tree_height)
-                || col_start.sees_tree(x, tree_height)
-                || col_end.sees_tree(x, tree_height)
-            {
-                visible += 1;
-            }
+    /// builds the forest from its rows, which all need to be equally wide
+    pub fn from_rows(rows: Vec<Vec<u8>>) -> Result<Self> {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(anyhow!(
+                "row {} has {} trees, but the first row has {}",
+                i + 1,
+                row.len(),
+                width
+            ));
         }
+        Ok(Forest {
+            width,
+            height: rows.len(),
+            trees: rows.concat(),
+        })
     }
 
-    info!("number of visible trees: {}", visible);
+    pub fn width(&self) -> usize {
+        self.width
+    }
 
-    Ok(())
-}
+    pub fn height(&self) -> usize {
+        self.height
+    }
 
-fn solve_2(parser: TreeParser) -> Result<()> {
-    let mut tree_grid = Vec::new();
-    for (x, y, tree_height) in parser {
-        debug!("tree at ({},{}) has height {}", x, y, tree_height);
-        if tree_grid.len() < (x + 1) as usize {
-            tree_grid.push(Vec::new());
+    pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+        if row < self.height && col < self.width {
+            Some(self.trees[row * self.width + col])
+        } else {
+            None
         }
-        tree_grid[x as usize].push(TreeView::init(tree_height as usize));
     }
 
-    let tree_len = tree_grid.len();
-
-    let mut curr_views_top: Vec<Vec<u32>> = (0..tree_len)
-        .map(|_| vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
-        .collect(); // if I'm a tree of this height, how many trees can I see?
-    let mut prev_height_top: Vec<usize> = (0..tree_len).map(|_| 0).collect();
-
-    for x in 0..tree_len {
-        let mut curr_views_left = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0]; // if I'm a tree of this height, how many trees can I see?
-        let curr_row = tree_grid.get_mut(x).unwrap();
-        let mut prev_height_left = curr_row.get(0).unwrap().height;
-
-        for y in 0..tree_len {
-            if y != 0 {
-                curr_views_left = curr_views_left
-                    .iter_mut()
-                    .enumerate()
-                    .map(|(height, num_trees)| {
-                        if height <= prev_height_left {
-                            1
-                        } else {
-                            *num_trees + 1
-                        }
-                    })
-                    .collect();
-            }
-            if x != 0 {
-                curr_views_top[y] = curr_views_top[y]
-                    .iter_mut()
-                    .enumerate()
-                    .map(|(height, num_trees)| {
-                        if height <= prev_height_top[y] {
-                            1
-                        } else {
-                            *num_trees + 1
-                        }
-                    })
-                    .collect();
+    /// computes visibility and viewing distances with one pass per direction - each pass keeps a
+    /// stack of the trees that could still block the view, so every tree is pushed and popped once
+    pub fn sweep(&self) -> Sweep {
+        let mut sweep = Sweep {
+            visible: vec![false; self.trees.len()],
+            scores: vec![1; self.trees.len()],
+        };
+
+        let mut stack = Vec::new();
+        for row in 0..self.height {
+            let start = row * self.width;
+            for cols in [
+                &mut (0..self.width) as &mut dyn Iterator<Item = usize>,
+                &mut (0..self.width).rev(),
+            ] {
+                stack.clear();
+                for (pos, col) in cols.enumerate() {
+                    self.look_back(&mut stack, pos, start + col, &mut sweep);
+                }
             }
-            let curr_tree = curr_row.get_mut(y).unwrap();
-            curr_tree.up = curr_views_top[y][curr_tree.height];
-            curr_tree.left = curr_views_left[curr_tree.height];
-            prev_height_top[y] = curr_tree.height;
-            prev_height_left = curr_tree.height;
         }
-    }
 
-    let mut curr_views_bottom: Vec<Vec<u32>> = (0..tree_len)
-        .map(|_| vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
-        .collect(); // if I'm a tree of this height, how many trees can I see?
-    let mut prev_height_bottom: Vec<usize> = (0..tree_len).map(|_| 0).collect();
-
-    for x in (0..tree_len).rev() {
-        let mut curr_views_right = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0]; // if I'm a tree of this height, how many trees can I see?
-        let curr_row = tree_grid.get_mut(x).unwrap();
-        let mut prev_height_right = curr_row.get(tree_len - 1).unwrap().height;
-
-        for y in (0..tree_len).rev() {
-            if y != tree_len - 1 {
-                curr_views_right = curr_views_right
-                    .iter_mut()
-                    .enumerate()
-                    .map(|(height, num_trees)| {
-                        if height <= prev_height_right {
-                            1
-                        } else {
-                            *num_trees + 1
-                        }
-                    })
-                    .collect();
-            }
-            if x != tree_len - 1 {
-                curr_views_bottom[y] = curr_views_bottom[y]
-                    .iter_mut()
-                    .enumerate()
-                    .map(|(height, num_trees)| {
-                        if height <= prev_height_bottom[y] {
-                            1
-                        } else {
-                            *num_trees + 1
-                        }
-                    })
-                    .collect();
+        // all columns at once, so that the trees are visited in memory order
+        let mut stacks = vec![Vec::new(); self.width];
+        for rows in [
+            &mut (0..self.height) as &mut dyn Iterator<Item = usize>,
+            &mut (0..self.height).rev(),
+        ] {
+            stacks.iter_mut().for_each(|stack| stack.clear());
+            for (pos, row) in rows.enumerate() {
+                for (col, stack) in stacks.iter_mut().enumerate() {
+                    self.look_back(stack, pos, row * self.width + col, &mut sweep);
+                }
             }
-            let curr_tree = curr_row.get_mut(y).unwrap();
-            curr_tree.down = curr_views_bottom[y][curr_tree.height];
-            curr_tree.right = curr_views_right[curr_tree.height];
-            prev_height_bottom[y] = curr_tree.height;
-            prev_height_right = curr_tree.height;
         }
+        sweep
     }
 
-    let mut max_score = 0;
-    for tree_row in tree_grid.iter() {
-        for tree in tree_row.iter() {
-            max_score = std::cmp::max(max_score, tree.get_score());
+    /// looks back from the tree at index i, which is at position pos of its line of sight - the
+    /// stack holds the position and height of the trees before it that can still block the view,
+    /// with strictly decreasing heights
+    fn look_back(&self, stack: &mut Vec<(usize, u8)>, pos: usize, i: usize, sweep: &mut Sweep) {
+        let height = self.trees[i];
+        // lower trees can't block the view of this tree or of any tree after it
+        while stack.last().is_some_and(|&(_, other)| other < height) {
+            stack.pop();
         }
+        let distance = match stack.last() {
+            Some(&(blocking, _)) => pos - blocking,
+            None => {
+                sweep.visible[i] = true;
+                pos
+            }
+        };
+        sweep.scores[i] *= distance as u64;
+        // from now on, this tree blocks the view before any tree of the same height does
+        if stack.last().is_some_and(|&(_, other)| other == height) {
+            stack.pop();
+        }
+        stack.push((pos, height));
     }
 
-    info!("top score: {}", max_score);
-
-    Ok(())
-}
+    /// for every tree, whether it can be seen from outside the forest
+    pub fn visibility_mask(&self) -> Vec<Vec<bool>> {
+        self.rows(self.sweep().visible)
+    }
 
-struct TreeView {
-    height: usize,
-    left: u32,
-    right: u32,
-    up: u32,
-    down: u32,
-}
+    /// for every tree, the product of its viewing distances in all four directions
+    pub fn scenic_scores(&self) -> Vec<Vec<u64>> {
+        self.rows(self.sweep().scores)
+    }
 
-impl TreeView {
-    fn init(height: usize) -> Self {
-        TreeView {
-            height,
-            left: 0,
-            right: 0,
-            up: 0,
-            down: 0,
+    /// (row, col) and scenic score of the n trees with the highest scores, best first
+    pub fn top_spots(&self, n: usize) -> Vec<((usize, usize), u64)> {
+        let best_first = |(i, a): &(usize, u64), (j, b): &(usize, u64)| b.cmp(a).then(i.cmp(j));
+        let mut spots: Vec<_> = self.sweep().scores.into_iter().enumerate().collect();
+        if n < spots.len() {
+            spots.select_nth_unstable_by(n, best_first);
+            spots.truncate(n);
         }
+        spots.sort_unstable_by(best_first);
+        spots
+            .into_iter()
+            .map(|(i, score)| ((i / self.width, i % self.width), score))
+            .collect()
     }
 
-    fn get_score(&self) -> u32 {
-        let score = self.left * self.right * self.up * self.down;
-        debug!("tree of height {} with score {}", self.height, score);
-        score
+    fn rows<T: Clone>(&self, values: Vec<T>) -> Vec<Vec<T>> {
+        values
+            .chunks(self.width.max(1))
+            .map(|row| row.to_vec())
+            .collect()
     }
 }
 
-#[derive(Debug)]
-struct Viewpoint {
-    tree_visibilities: Vec<i32>, // each index represents the distance in hops until which we can see the corresponding height
-    is_start: bool,
+/// draws the values with one character per value - darker characters for higher values
+pub fn heatmap_ascii(values: &[Vec<u64>]) -> String {
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&v| {
+                    let i = (v as u128 * (HEATMAP_RAMP.len() - 1) as u128 / max as u128) as usize;
+                    HEATMAP_RAMP[i] as char
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-impl Viewpoint {
-    fn init(is_start: bool) -> Self {
-        Viewpoint {
-            tree_visibilities: vec![-1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
-            is_start,
-        }
-    }
-
-    fn add_tree(&mut self, num_hops: u32, tree_height: u32) {
-        if self.tree_visibilities[tree_height as usize] == -1
-            || self.tree_visibilities[tree_height as usize] > num_hops as i32
-        {
-            self.tree_visibilities[tree_height as usize] = num_hops as i32;
-        }
-    }
-
-    fn sees_tree(&self, index: usize, height: &u32) -> bool {
-        let tree_visible = self.tree_visibilities[*height as usize..]
+/// encodes the values as a plain (P2) PGM image, scaled to grey levels 0 to 255
+pub fn heatmap_pgm(values: &[Vec<u64>]) -> String {
+    let height = values.len();
+    let width = values.first().map(|row| row.len()).unwrap_or(0);
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let mut pgm = format!("P2\n{} {}\n255\n", width, height);
+    for row in values {
+        let grey: Vec<String> = row
             .iter()
-            .all(|hops| {
-                (self.is_start && hops >= &(index as i32))
-                    || (!self.is_start && hops >= &(98 - index as i32))
-                    || *hops == -1
-            });
-        debug!(
-            "checking if tree {} with height {} is visible for: {:?} --> {}",
-            index, height, self, tree_visible
-        );
-        tree_visible
+            .map(|&v| (v as u128 * 255 / max as u128).to_string())
+            .collect();
+        pgm.push_str(&grey.join(" "));
+        pgm.push('\n');
     }
+    pgm
 }
 
 struct TreeParser {
-    in_reader: BufReader<File>,
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
     char_queue: VecDeque<char>,
     curr_x: u32,
     curr_y: u32,
@@ -245,62 +240,130 @@ struct TreeParser {
 
 impl TreeParser {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
-
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
+        let lines = input::read_lines(input)?;
 
         Ok(TreeParser {
-            in_reader,
+            lines,
+            line_number: 0,
             char_queue: VecDeque::new(),
             curr_x: 0,
             curr_y: 0,
         })
     }
 
-    fn get_next_line(&mut self) -> Option<()> {
-        let mut line = String::new();
-        let bytes_read = self
-            .in_reader
-            .read_line(&mut line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 || line == "\n" {
-            return None; // EOF
+    fn get_next_line(&mut self) -> Option<Result<()>> {
+        let line = match self.lines.next()? {
+            Ok(line) if line.is_blank() => return None, // EOF
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        if self.line_number > 0 {
+            self.curr_x += 1;
         }
-        self.char_queue = line.chars().collect();
+        self.line_number = line.number;
+        self.char_queue = line.text.chars().collect();
         self.curr_y = 0;
-        Some(())
+        Some(Ok(()))
     }
 }
 
 impl Iterator for TreeParser {
-    type Item = (u32, u32, u32); // x, y, height
+    type Item = Result<(u32, u32, u32)>; // x, y, height
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next_char_option = self.char_queue.pop_front();
-        let next_char = if next_char_option.is_none() {
-            if self.get_next_line().is_some() {
-                self.char_queue.pop_front().unwrap()
-            } else {
-                return None;
-            }
-        } else if next_char_option.unwrap() == '\n' {
-            self.curr_x += 1;
-            if self.get_next_line().is_some() {
-                self.char_queue.pop_front().unwrap()
-            } else {
-                return None;
-            }
-        } else {
+        let next_char = if let Some(c) = self.char_queue.pop_front() {
             self.curr_y += 1;
-            next_char_option.unwrap()
+            c
+        } else {
+            if let Err(e) = self.get_next_line()? {
+                return Some(Err(e));
+            }
+            self.char_queue.pop_front()?
         };
 
         if let Some(height) = next_char.to_digit(10) {
-            Some((self.curr_x, self.curr_y, height))
+            Some(Ok((self.curr_x, self.curr_y, height)))
         } else {
-            panic!("couldn't parse height from character: {}", next_char)
+            Some(Err(anyhow!(
+                "couldn't parse height from character '{}' in line {}",
+                next_char,
+                self.line_number
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::task_solver::day_8::{heatmap_ascii, heatmap_pgm, Forest};
+
+    /// visibility and scenic score of a single tree, by looking along all four lines of sight
+    fn look_around(rows: &[Vec<u8>], row: usize, col: usize) -> (bool, u64) {
+        let height = rows[row][col];
+        let lines: [Vec<u8>; 4] = [
+            (0..row).rev().map(|r| rows[r][col]).collect(),
+            (row + 1..rows.len()).map(|r| rows[r][col]).collect(),
+            (0..col).rev().map(|c| rows[row][c]).collect(),
+            (col + 1..rows[row].len()).map(|c| rows[row][c]).collect(),
+        ];
+        let visible = lines.iter().any(|line| line.iter().all(|&h| h < height));
+        let score = lines
+            .iter()
+            .map(|line| match line.iter().position(|&h| h >= height) {
+                Some(i) => i as u64 + 1,
+                None => line.len() as u64,
+            })
+            .product();
+        (visible, score)
+    }
+
+    fn forest(rows: &[&str]) -> anyhow::Result<Forest> {
+        Forest::from_rows(
+            rows.iter()
+                .map(|row| row.bytes().map(|b| b - b'0').collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn forest_test() {
+        let sample = forest(&["30373", "25512", "65332", "33549", "35390"]).unwrap();
+        let visible = sample
+            .visibility_mask()
+            .into_iter()
+            .flatten()
+            .filter(|&v| v);
+        assert_eq!(visible.count(), 21);
+        assert_eq!(sample.scenic_scores()[3][2], 8);
+        assert_eq!(sample.top_spots(2), vec![((3, 2), 8), ((2, 1), 6)]);
+
+        // non-square forests
+        let wide = forest(&["1234", "1054", "9999"]).unwrap();
+        assert_eq!((wide.width(), wide.height()), (4, 3));
+        assert_eq!(wide.get(1, 2), Some(5));
+        assert_eq!(wide.get(3, 0), None);
+        assert_eq!(wide.visibility_mask()[1], vec![true, false, true, true]);
+        assert!(forest(&["123", "12"]).is_err());
+
+        let scores = vec![vec![0, 4], vec![8, 2]];
+        assert_eq!(heatmap_ascii(&scores), " =\n@:");
+        assert_eq!(heatmap_pgm(&scores), "P2\n2 2\n255\n0 127\n255 63\n");
+    }
+
+    proptest! {
+        #[test]
+        fn sweep_matches_looking_around(rows in (1..12usize, 1..12usize).prop_flat_map(|(w, h)| {
+            prop::collection::vec(prop::collection::vec(0..10u8, w), h)
+        })) {
+            let forest = Forest::from_rows(rows.clone()).unwrap();
+            let (mask, scores) = (forest.visibility_mask(), forest.scenic_scores());
+            for row in 0..rows.len() {
+                for col in 0..rows[0].len() {
+                    prop_assert_eq!((mask[row][col], scores[row][col]), look_around(&rows, row, col));
+                }
+            }
         }
     }
 }
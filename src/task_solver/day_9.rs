@@ -1,157 +1,268 @@
 use anyhow::{anyhow, Context, Result};
-use core::panic;
 use log::{debug, info};
-use regex::Regex;
-use std::{
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
-
-pub fn solve(task: u8, input: String) -> Result<()> {
-    let parser = MoveParser::init(input).context("failed to instantiate parser")?;
+use std::{collections::HashSet, fs::File, io::BufReader, str::FromStr};
+
+use super::util::input::{self, Line, Lines};
 
-    let (mut head_pos, mut tail_pos) = ((0i32, 0i32), (0i32, 0i32));
-    let rope_len = match task {
+const RE_MOVE: &str = r"^(?P<dir>[UD][LR]?|[LR]) (?P<num>\d+)$";
+
+pub fn solve(task: u8, input: String) -> Result<String> {
+    let knots = match task {
         1 => 2,
         2 => 10,
         _ => return Err(anyhow!("task doesn't exist!")),
     };
-    let mut rope_accum: Vec<(i32, i32)> = (1..rope_len).map(|_| (0i32, 0i32)).collect(); // [H-1, ..., T]
-    let mut visited = HashSet::new();
-    visited.insert(tail_pos);
+    solve_with(input, knots)
+}
 
-    for (dir, num) in parser {
-        debug!("");
-        debug!("--------- {:?} {} ---------", dir, num);
-        for _ in 0..num {
-            let mut curr_motion = dir.get_motion();
-            head_pos = update_pos(head_pos, curr_motion);
-            for accum in rope_accum.iter_mut() {
-                // update accum
-                let accum_new = (accum.0 + curr_motion.0, accum.1 + curr_motion.1);
-                // check if move required
-                if accum_new.0.abs() > 1 || accum_new.1.abs() > 1 {
-                    curr_motion = (accum_new.0.clamp(-1, 1), accum_new.1.clamp(-1, 1));
-                    *accum = (accum_new.0 - curr_motion.0, accum_new.1 - curr_motion.1);
-                } else {
-                    curr_motion = (0, 0);
-                    *accum = accum_new;
-                }
-            }
-            tail_pos = update_pos(tail_pos, curr_motion);
-            let is_new = visited.insert(tail_pos);
-            if is_new {
-                debug!("MOVED TAIL BY {:?}", curr_motion);
-            }
+/// pulls a rope with the given number of knots through the moves and counts the fields its tail visits
+pub fn solve_with(input: String, knots: usize) -> Result<String> {
+    let parser = MoveParser::init(input).context("failed to instantiate parser")?;
+    let mut rope = Rope::new(knots)?;
 
-            debug!(
-                "H: {:?}; T: {:?}; visited: {}",
-                head_pos,
-                tail_pos,
-                visited.len()
-            );
-        }
+    for dir_move in parser {
+        let (dir, num) = dir_move?;
+        debug!("--------- {:?} {} ---------", dir, num);
+        rope.apply(dir, num);
+        debug!("H: {:?}; T: {:?}", rope.head(), rope.tail());
     }
+    debug!("\n{}", rope.render(&[knots - 1]));
 
-    info!("number of visited fields: {}", visited.len());
+    let visited = rope.trail(knots - 1).len();
+    info!("number of visited fields: {}", visited);
 
-    Ok(())
+    Ok(visited.to_string())
 }
 
-fn update_pos((x, y): (i32, i32), (m_x, m_y): (i32, i32)) -> (i32, i32) {
-    (x + m_x, y + m_y)
-}
+/// (x, y) with y pointing up
+type Position = (i32, i32);
 
-#[derive(Debug)]
-enum Direction {
-    UP,
-    DOWN,
-    LEFT,
-    RIGHT,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
 impl Direction {
-    fn get_motion(&self) -> (i32, i32) {
+    fn get_motion(&self) -> Position {
         match self {
-            Direction::UP => (1, 0),
-            Direction::DOWN => (-1, 0),
-            Direction::LEFT => (0, -1),
-            Direction::RIGHT => (0, 1),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
         }
     }
 }
 
 impl FromStr for Direction {
-    type Err = ();
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "U" => Ok(Direction::UP),
-            "D" => Ok(Direction::DOWN),
-            "L" => Ok(Direction::LEFT),
-            "R" => Ok(Direction::RIGHT),
-            _ => Err(()),
+            "U" => Ok(Direction::Up),
+            "D" => Ok(Direction::Down),
+            "L" => Ok(Direction::Left),
+            "R" => Ok(Direction::Right),
+            "UL" => Ok(Direction::UpLeft),
+            "UR" => Ok(Direction::UpRight),
+            "DL" => Ok(Direction::DownLeft),
+            "DR" => Ok(Direction::DownRight),
+            _ => Err(anyhow!("unknown direction {:?}", s)),
+        }
+    }
+}
+
+/// a rope of knots starting on top of each other at the origin, which remembers where each knot has been
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<Position>,
+    trails: Vec<HashSet<Position>>,
+}
+
+impl Rope {
+    pub fn new(knots: usize) -> Result<Self> {
+        if knots == 0 {
+            return Err(anyhow!("a rope needs at least one knot"));
+        }
+        Ok(Rope {
+            knots: vec![(0, 0); knots],
+            trails: vec![HashSet::from([(0, 0)]); knots],
+        })
+    }
+
+    pub fn knots(&self) -> &[Position] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Position {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Position {
+        self.knots[self.knots.len() - 1]
+    }
+
+    /// fields visited by the knot at the given index, 0 being the head
+    pub fn trail(&self, knot: usize) -> &HashSet<Position> {
+        &self.trails[knot]
+    }
+
+    pub fn apply(&mut self, dir: Direction, num: u32) {
+        for _ in 0..num {
+            self.step(dir);
+        }
+    }
+
+    /// moves the head by one field and lets every other knot catch up with the one before it
+    pub fn step(&mut self, dir: Direction) {
+        let (dx, dy) = dir.get_motion();
+        self.knots[0] = (self.knots[0].0 + dx, self.knots[0].1 + dy);
+        self.trails[0].insert(self.knots[0]);
+
+        for i in 1..self.knots.len() {
+            let (ahead, knot) = (self.knots[i - 1], self.knots[i]);
+            let (dx, dy) = (ahead.0 - knot.0, ahead.1 - knot.1);
+            if dx.abs() <= 1 && dy.abs() <= 1 {
+                break; // still touching, so none of the following knots move either
+            }
+            self.knots[i] = (knot.0 + dx.signum(), knot.1 + dy.signum());
+            self.trails[i].insert(self.knots[i]);
+        }
+    }
+
+    /// ascii map of the rope on top of the trails of the given knots - the head is H, the tail T
+    /// (if there's more than one knot), the knots in between are labelled 1-9 and a-z, the start
+    /// is s and visited fields are #
+    pub fn render(&self, trails: &[usize]) -> String {
+        let fields: Vec<Position> = trails
+            .iter()
+            .flat_map(|&knot| self.trails[knot].iter().copied())
+            .chain(self.knots.iter().copied())
+            .chain([(0, 0)])
+            .collect();
+        let (min_x, max_x) = min_max(fields.iter().map(|&(x, _)| x));
+        let (min_y, max_y) = min_max(fields.iter().map(|&(_, y)| y));
+
+        let width = (max_x - min_x + 1) as usize;
+        let mut rows = vec![vec!['.'; width]; (max_y - min_y + 1) as usize];
+        let mut draw =
+            |(x, y): Position, c: char| rows[(max_y - y) as usize][(x - min_x) as usize] = c;
+
+        for &knot in trails {
+            self.trails[knot].iter().for_each(|&field| draw(field, '#'));
+        }
+        draw((0, 0), 's');
+        // later knots first, so that the ones closer to the head end up on top
+        for (i, &knot) in self.knots.iter().enumerate().rev() {
+            draw(knot, self.label(i));
+        }
+
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn label(&self, knot: usize) -> char {
+        match knot {
+            0 => 'H',
+            _ if knot == self.knots.len() - 1 => 'T',
+            _ => char::from_digit(knot as u32, 36).unwrap_or('*'),
         }
     }
 }
 
+fn min_max(values: impl Iterator<Item = i32>) -> (i32, i32) {
+    values.fold((i32::MAX, i32::MIN), |(min, max), v| {
+        (min.min(v), max.max(v))
+    })
+}
+
 struct MoveParser {
-    in_reader: BufReader<File>,
-    line: String,
+    lines: Lines<BufReader<File>>,
 }
 
 impl MoveParser {
     fn init(input: String) -> Result<Self> {
-        // open input file
-        let in_file = File::open(input).context(format!("Failed to read input"))?;
+        let lines = input::read_lines(input)?;
 
-        // uses a reader buffer
-        let in_reader = BufReader::new(in_file);
-        let line = String::new();
-
-        Ok(MoveParser { in_reader, line })
+        Ok(MoveParser { lines })
     }
 }
 
+fn parse_move(line: &Line) -> Result<(Direction, u32)> {
+    let (dir, num) = line.captures(RE_MOVE)?.extract(["dir", "num"])?;
+    Ok((dir, num))
+}
+
 impl Iterator for MoveParser {
-    type Item = (Direction, u8);
+    type Item = Result<(Direction, u32)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let re_move = Regex::new(r"^(?P<dir>[UDRL]) (?P<num>\d+)").unwrap();
-
-        let bytes_read = self
-            .in_reader
-            .read_line(&mut self.line)
-            .expect("Failed to read line in input file");
-        if bytes_read == 0 || self.line == "\n" {
-            return None; // EOF
+        match self.lines.next()? {
+            Ok(line) if line.is_blank() => None, // EOF
+            Ok(line) => Some(parse_move(&line)),
+            Err(e) => Some(Err(e)),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::{
+        day_9::{parse_move, Direction, Rope},
+        util::input::Line,
+    };
 
-        if re_move.is_match(&self.line) {
-            let move_captures = re_move
-                .captures(&self.line)
-                .expect("move regex failed to capture line");
-            let dir_str = move_captures
-                .name("dir")
-                .expect("move regex didn't contain expected named capture group")
-                .as_str();
-            let dir = Direction::from_str(dir_str)
-                .expect("failed to parse direction from captured string");
-
-            let num = move_captures
-                .name("num")
-                .expect("move regex didn't contain expected named capture group")
-                .as_str()
-                .parse::<u8>()
-                .expect("failed to parse u8 number from captured string");
-
-            self.line.clear();
-
-            Some((dir, num))
-        } else {
-            panic!("line didn't match regex: {}", self.line);
+    fn pull(knots: usize, moves: &str) -> Rope {
+        let mut rope = Rope::new(knots).unwrap();
+        for m in moves.split(',') {
+            let (dir, num) = m.split_once(' ').unwrap();
+            rope.apply(dir.parse().unwrap(), num.parse().unwrap());
         }
+        rope
+    }
+
+    #[test]
+    fn larger_example() {
+        let rope = pull(10, "R 5,U 8,L 8,D 3,R 17,D 10,L 25,U 20");
+        assert_eq!(rope.trail(9).len(), 36);
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        assert!("UX".parse::<Direction>().is_err());
+        let rope = pull(2, "UR 3,DL 1");
+        assert_eq!(rope.knots(), [(2, 2), (2, 2)]);
+        assert_eq!(rope.trail(0).len(), 4);
+        assert_eq!(rope.trail(1).len(), 3);
+    }
+
+    #[test]
+    fn parse_moves() {
+        let line = |text: &str| Line {
+            number: 1,
+            text: text.to_string(),
+        };
+        assert!(parse_move(&line("R 5")).is_ok());
+        assert!(parse_move(&line("R 5 junk")).is_err());
+        assert!(parse_move(&line("R 5x")).is_err());
+    }
+
+    #[test]
+    fn render() {
+        let rope = pull(3, "R 4,U 1");
+        assert_eq!(rope.render(&[2]), "....H\ns#T1.");
+        assert_eq!(rope.render(&[]), "....H\ns.T1.");
     }
 }
@@ -1,75 +1,15 @@
 use std::ops::Add;
 
-use regex::Regex;
+pub mod bitset;
+pub mod capture;
+pub mod cycle;
+pub mod input;
+pub mod memo;
+pub mod ocr;
+pub mod search;
+pub mod top_k;
 
-#[derive(Debug)]
-/// A sorted list of constant size
-pub struct SortedList<T: PartialOrd> {
-    capacity: usize,
-    items: Vec<T>,
-}
-
-impl<T: PartialOrd> SortedList<T> {
-    pub fn new(capacity: usize) -> SortedList<T> {
-        SortedList {
-            capacity,
-            items: Vec::with_capacity(capacity + 1),
-        }
-    }
-
-    /// inserts an item into the list if possible
-    pub fn insert(&mut self, item: T) {
-        let mut insert_i = 0;
-        for (i, curr) in self.items.iter().enumerate() {
-            if &item > curr {
-                break;
-            } else {
-                insert_i = i + 1;
-            }
-        }
-
-        if insert_i < self.capacity {
-            self.items.insert(insert_i, item);
-            self.items.truncate(self.capacity);
-        }
-    }
-
-    pub fn fold<U>(&self, init: U, f: &dyn Fn(&T, U) -> U) -> U {
-        let mut acc = init;
-        for curr in self.items.iter() {
-            acc = f(curr, acc);
-        }
-        acc
-    }
-}
-
-pub fn try_capture_and_parse<T>(
-    re: &Regex,
-    line: &str,
-    group_name: &str,
-    parse_fn: &dyn Fn(&str) -> T,
-) -> Option<T> {
-    let re_captures = re.captures(line).expect("regex failed to capture line");
-    if let Some(captured_str) = re_captures.name(group_name) {
-        Some(parse_fn(captured_str.as_str()))
-    } else {
-        None
-    }
-}
-
-pub fn capture_and_parse<T>(
-    re: &Regex,
-    line: &str,
-    group_name: &str,
-    parse_fn: &dyn Fn(&str) -> T,
-) -> T {
-    let re_captures = re.captures(line).expect("regex failed to capture line");
-    let captured_str = re_captures
-        .name(group_name)
-        .expect("regex didn't contain expected named capture group")
-        .as_str();
-    parse_fn(captured_str)
-}
+pub use bitset::{BitSet, GrowableBitSet};
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct Point<T> {
@@ -102,3 +42,31 @@ impl<T: Add<Output = T>> Add for Point<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::task_solver::util::Point;
+
+    fn point() -> impl Strategy<Value = Point<i64>> {
+        (-1000000..1000000i64, -1000000..1000000i64).prop_map(Point::of_tuple)
+    }
+
+    proptest! {
+        #[test]
+        fn point_addition_laws(a in point(), b in point(), c in point()) {
+            let zero = Point::of_tuple((0, 0));
+            prop_assert_eq!(a + zero, a);
+            prop_assert_eq!(a + b, b + a);
+            prop_assert_eq!((a + b) + c, a + (b + c));
+        }
+
+        #[test]
+        fn point_adds_coordinates(x in any::<i32>(), y in any::<i32>(), dx in -1..=1i32, dy in -1..=1i32) {
+            prop_assume!(x.checked_add(dx).is_some() && y.checked_add(dy).is_some());
+            let p = Point::of_tuple((x, y)) + Point::of_tuple((dx, dy));
+            prop_assert_eq!((*p.get_x(), *p.get_y()), (x + dx, y + dy));
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use std::{
+    fmt,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Shl, Shr, Sub},
+};
+
+use num::{PrimInt, Unsigned};
+
+/// A set of integers below the bit width of B, stored in a single integer - B is unsigned, so
+/// that the arithmetic on the bits can't overflow once the top bit is set
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitSet<B = u64>(B);
+
+impl<B: PrimInt + Unsigned> BitSet<B> {
+    /// number of elements the set can hold
+    pub const WIDTH: usize = std::mem::size_of::<B>() * 8;
+
+    pub fn new() -> Self {
+        BitSet(B::zero())
+    }
+
+    pub const fn from_bits(bits: B) -> Self {
+        BitSet(bits)
+    }
+
+    /// set containing 0..n
+    pub fn full(n: usize) -> Self {
+        assert!(n <= Self::WIDTH, "bitset can't hold {} elements", n);
+        if n == Self::WIDTH {
+            BitSet(!B::zero())
+        } else {
+            BitSet((B::one() << n) - B::one())
+        }
+    }
+
+    pub fn bits(&self) -> B {
+        self.0
+    }
+
+    fn bit(i: usize) -> B {
+        assert!(i < Self::WIDTH, "{} is out of range for bitset", i);
+        B::one() << i
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < Self::WIDTH && self.0 & Self::bit(i) != B::zero()
+    }
+
+    /// returns true if the element wasn't in the set yet
+    pub fn insert(&mut self, i: usize) -> bool {
+        let is_new = !self.contains(i);
+        self.0 = self.0 | Self::bit(i);
+        is_new
+    }
+
+    /// returns true if the element was in the set
+    pub fn remove(&mut self, i: usize) -> bool {
+        let was_present = self.contains(i);
+        self.0 = self.0 & !Self::bit(i);
+        was_present
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == B::zero()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        BitSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        BitSet(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        BitSet(self.0 & !other.0)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        BitSet(self.0 ^ other.0)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0 & !other.0 == B::zero()
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0 & other.0 == B::zero()
+    }
+
+    /// smallest element of the set
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    /// iterates over the elements in ascending order
+    pub fn iter(&self) -> Iter<B> {
+        Iter(self.0)
+    }
+
+    /// iterates over all 2^len subsets, starting with the set itself and ending with the empty set
+    pub fn subsets(&self) -> Subsets<B> {
+        Subsets {
+            mask: self.0,
+            next: Some(self.0),
+        }
+    }
+}
+
+/// Iterator over the elements of a BitSet
+pub struct Iter<B>(B);
+
+impl<B: PrimInt + Unsigned> Iterator for Iter<B> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == B::zero() {
+            return None;
+        }
+        let i = self.0.trailing_zeros() as usize;
+        self.0 = self.0 & (self.0 - B::one()); // clear lowest bit
+        Some(i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+/// Iterator over the subsets of a BitSet
+pub struct Subsets<B> {
+    mask: B,
+    next: Option<B>,
+}
+
+impl<B: PrimInt + Unsigned> Iterator for Subsets<B> {
+    type Item = BitSet<B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let curr = self.next?;
+        self.next = if curr == B::zero() {
+            None
+        } else {
+            Some((curr - B::one()) & self.mask)
+        };
+        Some(BitSet(curr))
+    }
+}
+
+impl<B: PrimInt + Unsigned> IntoIterator for BitSet<B> {
+    type Item = usize;
+    type IntoIter = Iter<B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<B: PrimInt + Unsigned> FromIterator<usize> for BitSet<B> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<B: PrimInt + Unsigned> Extend<usize> for BitSet<B> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for i in iter {
+            self.insert(i);
+        }
+    }
+}
+
+impl<B: PrimInt + Unsigned> fmt::Debug for BitSet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<B: PrimInt + Unsigned> BitOr for BitSet<B> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(&rhs)
+    }
+}
+
+impl<B: PrimInt + Unsigned> BitOrAssign for BitSet<B> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(&rhs);
+    }
+}
+
+impl<B: PrimInt + Unsigned> BitAnd for BitSet<B> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(&rhs)
+    }
+}
+
+impl<B: PrimInt + Unsigned> BitAndAssign for BitSet<B> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(&rhs);
+    }
+}
+
+impl<B: PrimInt + Unsigned> BitXor for BitSet<B> {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(&rhs)
+    }
+}
+
+impl<B: PrimInt + Unsigned> Sub for BitSet<B> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(&rhs)
+    }
+}
+
+/// adds n to every element - elements that end up out of range are dropped
+impl<B: PrimInt + Unsigned> Shl<usize> for BitSet<B> {
+    type Output = Self;
+
+    fn shl(self, n: usize) -> Self::Output {
+        BitSet(if n < Self::WIDTH {
+            self.0 << n
+        } else {
+            B::zero()
+        })
+    }
+}
+
+/// subtracts n from every element - elements below n are dropped
+impl<B: PrimInt + Unsigned> Shr<usize> for BitSet<B> {
+    type Output = Self;
+
+    fn shr(self, n: usize) -> Self::Output {
+        BitSet(if n < Self::WIDTH {
+            self.0 >> n
+        } else {
+            B::zero()
+        })
+    }
+}
+
+/// A set of integers of any size, stored in as many 64-bit words as needed
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct GrowableBitSet {
+    // trailing empty words are always trimmed, so equal sets have equal words
+    words: Vec<BitSet<u64>>,
+}
+
+const WORD_WIDTH: usize = BitSet::<u64>::WIDTH;
+
+impl GrowableBitSet {
+    pub fn new() -> Self {
+        GrowableBitSet { words: Vec::new() }
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.words
+            .get(i / WORD_WIDTH)
+            .is_some_and(|word| word.contains(i % WORD_WIDTH))
+    }
+
+    /// returns true if the element wasn't in the set yet
+    pub fn insert(&mut self, i: usize) -> bool {
+        if self.words.len() <= i / WORD_WIDTH {
+            self.words.resize(i / WORD_WIDTH + 1, BitSet::new());
+        }
+        self.words[i / WORD_WIDTH].insert(i % WORD_WIDTH)
+    }
+
+    /// returns true if the element was in the set
+    pub fn remove(&mut self, i: usize) -> bool {
+        let was_present = match self.words.get_mut(i / WORD_WIDTH) {
+            Some(word) => word.remove(i % WORD_WIDTH),
+            None => false,
+        };
+        self.trim();
+        was_present
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a ^ b)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// iterates over the elements in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(w, word)| word.iter().map(move |i| w * WORD_WIDTH + i))
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(BitSet<u64>, BitSet<u64>) -> BitSet<u64>) -> Self {
+        let len = self.words.len().max(other.words.len());
+        let word = |words: &[BitSet<u64>], i| words.get(i).copied().unwrap_or_default();
+        let mut set = GrowableBitSet {
+            words: (0..len)
+                .map(|i| f(word(&self.words, i), word(&other.words, i)))
+                .collect(),
+        };
+        set.trim();
+        set
+    }
+
+    fn trim(&mut self) {
+        while self.words.last().is_some_and(|word| word.is_empty()) {
+            self.words.pop();
+        }
+    }
+}
+
+impl FromIterator<usize> for GrowableBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for GrowableBitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for i in iter {
+            self.insert(i);
+        }
+    }
+}
+
+impl fmt::Debug for GrowableBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::task_solver::util::bitset::{BitSet, GrowableBitSet};
+
+    #[test]
+    fn bitset_test() {
+        let a: BitSet<u8> = [1, 3, 5].into_iter().collect();
+        let b: BitSet<u8> = [3, 4].into_iter().collect();
+        assert_eq!((a | b).iter().collect::<Vec<_>>(), vec![1, 3, 4, 5]);
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), vec![3]);
+        assert_eq!((a - b).len(), 2);
+        assert_eq!((a ^ b).len(), 3);
+        assert_eq!((a << 2).iter().collect::<Vec<_>>(), vec![3, 5, 7]);
+        assert_eq!((a >> 2).iter().collect::<Vec<_>>(), vec![1, 3]);
+        assert!(!a.contains(8));
+        assert_eq!(BitSet::<u8>::full(8).len(), 8);
+
+        let subsets: HashSet<_> = a.subsets().collect();
+        assert_eq!(subsets.len(), 8);
+        assert!(subsets.iter().all(|s| s.is_subset(&a)));
+        assert!(subsets.contains(&BitSet::new()));
+
+        // the top bit
+        let top: BitSet<u8> = [0, 7].into_iter().collect();
+        assert_eq!(top.iter().collect::<Vec<_>>(), vec![0, 7]);
+        assert_eq!(top.subsets().count(), 4);
+        assert_eq!(BitSet::<u8>::full(7).len(), 7);
+        assert_eq!(BitSet::<u8>::full(8).subsets().count(), 256);
+    }
+
+    #[test]
+    fn growable_bitset_test() {
+        let mut a: GrowableBitSet = [1, 200].into_iter().collect();
+        let b: GrowableBitSet = [1, 70].into_iter().collect();
+        assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), vec![1, 70, 200]);
+        assert_eq!(a.intersection(&b), [1].into_iter().collect());
+        assert!(a.insert(1000));
+        assert!(a.remove(1000) && a.remove(200));
+        // equal sets must be equal regardless of how far they grew before
+        assert_eq!(a, a.intersection(&b));
+        assert!(a.is_subset(&b) && !b.is_subset(&a));
+    }
+}
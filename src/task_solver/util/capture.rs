@@ -0,0 +1,183 @@
+use std::{cell::RefCell, collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<&'static str, Regex>> = RefCell::new(HashMap::new());
+}
+
+/// returns the compiled regex for the pattern - every pattern is only compiled once per thread
+pub fn regex(pattern: &'static str) -> Result<Regex> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(re) = cache.get(pattern) {
+            return Ok(re.clone()); // cheap, compiled regexes are reference counted
+        }
+        let re = Regex::new(pattern).context(format!("invalid regex /{}/", pattern))?;
+        cache.insert(pattern, re.clone());
+        Ok(re)
+    })
+}
+
+/// The groups of a single regex match - errors name the matched text and the failing group
+pub struct Captures<'t> {
+    source: String,
+    captures: regex::Captures<'t>,
+}
+
+/// matches the pattern against the text once
+pub fn captures<'t>(pattern: &'static str, text: &'t str) -> Result<Captures<'t>> {
+    captures_from(pattern, text, format!("'{}'", text))
+}
+
+/// same as captures, but errors describe the text as source
+pub fn captures_from<'t>(
+    pattern: &'static str,
+    text: &'t str,
+    source: String,
+) -> Result<Captures<'t>> {
+    match regex(pattern)?.captures(text) {
+        Some(captures) => Ok(Captures { source, captures }),
+        None => Err(anyhow!("{} doesn't match /{}/", source, pattern)),
+    }
+}
+
+impl<'t> Captures<'t> {
+    /// the raw text of a group - None if the group didn't participate in the match
+    pub fn as_str(&self, group: &str) -> Option<&'t str> {
+        self.captures.name(group).map(|m| m.as_str())
+    }
+
+    /// parses a group that has to be part of the match
+    pub fn get<T>(&self, group: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Debug,
+    {
+        self.try_get(group)?
+            .ok_or_else(|| anyhow!("group '{}' missing in {}", group, self.source))
+    }
+
+    /// parses an optional group - Ok(None) if it didn't participate in the match
+    pub fn try_get<T>(&self, group: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: fmt::Debug,
+    {
+        self.as_str(group)
+            .map(|s| {
+                s.parse().map_err(|e| {
+                    anyhow!(
+                        "failed to parse group '{}' ('{}') of {}: {:?}",
+                        group,
+                        s,
+                        self.source,
+                        e
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// parses several groups into a tuple, e.g. `extract::<(i32, i32)>(["x", "y"])`
+    pub fn extract<T, const N: usize>(&self, groups: [&str; N]) -> Result<T>
+    where
+        T: FromGroups<N>,
+    {
+        T::from_groups(self, groups)
+    }
+
+    pub fn parse<T: FromCaptures>(&self) -> Result<T> {
+        T::from_captures(self)
+    }
+}
+
+/// Types that can be built from N named groups of a match
+pub trait FromGroups<const N: usize>: Sized {
+    fn from_groups(captures: &Captures, groups: [&str; N]) -> Result<Self>;
+}
+
+macro_rules! impl_from_groups {
+    ($n:literal; $($t:ident $i:tt),+) => {
+        impl<$($t),+> FromGroups<$n> for ($($t,)+)
+        where
+            $($t: FromStr, $t::Err: fmt::Debug,)+
+        {
+            fn from_groups(captures: &Captures, groups: [&str; $n]) -> Result<Self> {
+                Ok(($(captures.get::<$t>(groups[$i])?,)+))
+            }
+        }
+    };
+}
+
+impl_from_groups!(1; A 0);
+impl_from_groups!(2; A 0, B 1);
+impl_from_groups!(3; A 0, B 1, C 2);
+impl_from_groups!(4; A 0, B 1, C 2, D 3);
+impl_from_groups!(5; A 0, B 1, C 2, D 3, E 4);
+impl_from_groups!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_groups!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_groups!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Structs that know which groups they're built from
+pub trait FromCaptures: Sized {
+    fn from_captures(captures: &Captures) -> Result<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::capture::{captures, FromCaptures};
+
+    const PATTERN: &str =
+        r"(?P<name>[a-z]+) at x=(?P<x>-?\d+), y=(?P<y>-?\d+)(?: \((?P<tag>.*)\))?";
+
+    #[derive(Debug, PartialEq)]
+    struct Sensor {
+        name: String,
+        pos: (i32, i32),
+    }
+
+    impl FromCaptures for Sensor {
+        fn from_captures(captures: &super::Captures) -> anyhow::Result<Self> {
+            Ok(Sensor {
+                name: captures.get("name")?,
+                pos: captures.extract(["x", "y"])?,
+            })
+        }
+    }
+
+    #[test]
+    fn extract_test() {
+        let c = captures(PATTERN, "sensor at x=-2, y=18 (first)").unwrap();
+        let (name, x, y): (String, i32, u8) = c.extract(["name", "x", "y"]).unwrap();
+        assert_eq!((name.as_str(), x, y), ("sensor", -2, 18));
+        assert_eq!(
+            c.try_get::<String>("tag").unwrap().as_deref(),
+            Some("first")
+        );
+        assert_eq!(
+            c.parse::<Sensor>().unwrap(),
+            Sensor {
+                name: "sensor".to_owned(),
+                pos: (-2, 18)
+            }
+        );
+    }
+
+    #[test]
+    fn error_test() {
+        assert!(captures(PATTERN, "no match")
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("'no match' doesn't match"));
+
+        let c = captures(PATTERN, "beacon at x=2, y=-1").unwrap();
+        assert!(c.try_get::<String>("tag").unwrap().is_none());
+        let missing = c.get::<String>("tag").unwrap_err().to_string();
+        assert_eq!(missing, "group 'tag' missing in 'beacon at x=2, y=-1'");
+        let invalid = c.get::<u32>("y").unwrap_err().to_string();
+        assert!(invalid.starts_with("failed to parse group 'y' ('-1') of 'beacon at x=2, y=-1'"));
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::HashMap, hash::Hash};
+
+use num::PrimInt;
+
+/// A cycle found in a sequence of `(state_key, metric)` pairs
+#[derive(Debug)]
+pub struct Cycle<M> {
+    /// index of the first step that is part of the cycle
+    pub start: usize,
+    /// number of steps after which the state repeats
+    pub period: usize,
+    /// metric of every step up to and including the first repetition
+    metrics: Vec<M>,
+}
+
+impl<M: PrimInt> Cycle<M> {
+    /// change of the metric over one full period
+    pub fn delta(&self) -> M {
+        self.metrics[self.start + self.period] - self.metrics[self.start]
+    }
+
+    /// computes the metric at step n - returns None if the result overflows M
+    pub fn extrapolate(&self, n: usize) -> Option<M> {
+        if n < self.metrics.len() {
+            return Some(self.metrics[n]);
+        }
+        let num_periods = M::from((n - self.start) / self.period)?;
+        let offset = (n - self.start) % self.period;
+        self.delta()
+            .checked_mul(&num_periods)?
+            .checked_add(&self.metrics[self.start + offset])
+    }
+}
+
+/// finds the first repeating state by remembering every key seen so far
+pub fn find_cycle<K, M, I>(sequence: I) -> Option<Cycle<M>>
+where
+    K: Hash + Eq,
+    I: IntoIterator<Item = (K, M)>,
+{
+    let mut seen_keys = HashMap::new();
+    let mut metrics = Vec::new();
+    for (i, (key, metric)) in sequence.into_iter().enumerate() {
+        metrics.push(metric);
+        if let Some(start) = seen_keys.insert(key, i) {
+            return Some(Cycle {
+                start,
+                period: i - start,
+                metrics,
+            });
+        }
+    }
+    None
+}
+
+/// finds the first repeating state using Brent's algorithm - only a single key is kept in memory,
+/// at the cost of running the sequence twice
+pub fn find_cycle_brent<K, M, I, F>(mut make_sequence: F) -> Option<Cycle<M>>
+where
+    K: Eq,
+    I: IntoIterator<Item = (K, M)>,
+    F: FnMut() -> I,
+{
+    // 1. find the period by moving the tortoise to the hare at every power of two
+    let mut keys = make_sequence().into_iter().map(|(key, _)| key);
+    let mut tortoise = keys.next()?;
+    let mut hare = keys.next()?;
+    let mut power = 1;
+    let mut period = 1;
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare;
+            power *= 2;
+            period = 0;
+        }
+        hare = keys.next()?;
+        period += 1;
+    }
+
+    // 2. find the start by walking two sequences that are one period apart
+    let tortoise_seq = make_sequence().into_iter();
+    let mut hare_seq = make_sequence().into_iter();
+    let mut metrics = Vec::new();
+    for _ in 0..period {
+        let (_, metric) = hare_seq.next()?;
+        metrics.push(metric);
+    }
+    for (start, ((tortoise, _), (hare, metric))) in tortoise_seq.zip(hare_seq).enumerate() {
+        metrics.push(metric);
+        if tortoise == hare {
+            return Some(Cycle {
+                start,
+                period,
+                metrics,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::cycle::{find_cycle, find_cycle_brent};
+
+    /// 0, 1, 2, 3, 4, 5, 2, 3, 4, 5, ... with a metric that grows by 10 per step
+    fn sequence() -> impl Iterator<Item = (u32, u64)> {
+        (0..).map(|i| (if i < 2 { i } else { 2 + (i - 2) % 4 }, 10 * i as u64))
+    }
+
+    #[test]
+    fn find_cycle_test() {
+        let cycle = find_cycle(sequence()).unwrap();
+        assert_eq!((cycle.start, cycle.period, cycle.delta()), (2, 4, 40));
+        assert_eq!(cycle.extrapolate(1), Some(10));
+        assert_eq!(cycle.extrapolate(1000000), Some(10000000));
+    }
+
+    #[test]
+    fn find_cycle_brent_test() {
+        let cycle = find_cycle_brent(sequence).unwrap();
+        assert_eq!((cycle.start, cycle.period, cycle.delta()), (2, 4, 40));
+        assert_eq!(cycle.extrapolate(1000000), Some(10000000));
+        assert!(find_cycle_brent(|| (0..100u32).map(|i| (i, i))).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::capture::{self, Captures};
+
+/// A single line of the input, without its line break
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// position of the line in the input, starting at 1
+    pub number: usize,
+    pub text: String,
+}
+
+impl Line {
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// parses the trimmed line - the error refers to the line number
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Debug,
+    {
+        self.text
+            .trim()
+            .parse()
+            .map_err(|e| anyhow!("failed to parse {}: {:?}", self, e))
+    }
+
+    /// matches the pattern against the line - errors refer to the line number
+    pub fn captures(&self, pattern: &'static str) -> Result<Captures<'_>> {
+        capture::captures_from(pattern, &self.text, self.to_string())
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} '{}'", self.number, self.text)
+    }
+}
+
+/// Iterates over the lines of the input
+pub struct Lines<R> {
+    reader: R,
+    number: usize,
+}
+
+/// opens the input file for reading it line-by-line
+pub fn read_lines(input: String) -> Result<Lines<BufReader<File>>> {
+    let in_file = File::open(&input).context(format!("Failed to read input {}", input))?;
+    Ok(Lines::new(BufReader::new(in_file)))
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn new(reader: R) -> Self {
+        Lines { reader, number: 0 }
+    }
+
+    /// reads all lines up to the next blank line or EOF - the blank line itself is skipped
+    pub fn section(&mut self) -> Result<Vec<Line>> {
+        let mut section = Vec::new();
+        for line in self.by_ref() {
+            let line = line?;
+            if line.is_blank() {
+                break;
+            }
+            section.push(line);
+        }
+        Ok(section)
+    }
+
+    /// groups the remaining lines into paragraphs separated by blank lines
+    pub fn paragraphs(self) -> Paragraphs<R> {
+        Paragraphs { lines: self }
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<Line>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::new();
+        match self.reader.read_line(&mut text) {
+            Ok(0) => None, // EOF
+            Ok(_) => {
+                self.number += 1;
+                let len = text.trim_end_matches(['\n', '\r']).len();
+                text.truncate(len);
+                Some(Ok(Line {
+                    number: self.number,
+                    text,
+                }))
+            }
+            Err(e) => Some(Err(e).context(format!("Failed to read line {}", self.number + 1))),
+        }
+    }
+}
+
+/// Iterates over blank-line-separated groups of lines - repeated blank lines are skipped
+pub struct Paragraphs<R> {
+    lines: Lines<R>,
+}
+
+impl<R: BufRead> Iterator for Paragraphs<R> {
+    type Item = Result<Vec<Line>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut paragraph = Vec::new();
+        for line in self.lines.by_ref() {
+            match line {
+                Ok(line) if line.is_blank() => {
+                    if !paragraph.is_empty() {
+                        break;
+                    }
+                }
+                Ok(line) => paragraph.push(line),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if paragraph.is_empty() {
+            None
+        } else {
+            Some(Ok(paragraph))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::input::Lines;
+
+    const INPUT: &str = "1000\n2000\n\n\n3000\r\n\n  4000\n";
+
+    #[test]
+    fn lines_test() {
+        let mut lines = Lines::new(INPUT.as_bytes());
+        let first = lines.section().unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].parse::<u32>().unwrap(), 2000);
+
+        let rest: Vec<_> = lines.map(|l| l.unwrap()).collect();
+        assert_eq!(rest.len(), 4);
+        assert_eq!((rest[1].number, rest[1].text.as_str()), (5, "3000"));
+        assert_eq!(rest[3].text, "  4000");
+        assert!(rest[0]
+            .parse::<u32>()
+            .unwrap_err()
+            .to_string()
+            .starts_with("failed to parse line 4 ''"));
+    }
+
+    #[test]
+    fn paragraphs_test() {
+        let paragraphs: Vec<_> = Lines::new(INPUT.as_bytes())
+            .paragraphs()
+            .map(|p| p.unwrap())
+            .collect();
+        assert_eq!(paragraphs.len(), 3);
+        assert_eq!(paragraphs[1][0].number, 5);
+        assert_eq!(paragraphs[2][0].parse::<u32>().unwrap(), 4000);
+    }
+}
@@ -0,0 +1,162 @@
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash};
+
+/// Cache for the results of a recursive function - counts hits and misses to find out why a search
+/// explodes
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    /// maximum number of cached results - once reached, new results aren't stored anymore
+    limit: Option<usize>,
+    hits: u64,
+    misses: u64,
+    skipped: u64,
+}
+
+/// Snapshot of the counters of a Memo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// results that weren't stored because the size limit was reached
+    pub skipped: u64,
+}
+
+impl<K: Hash + Eq, V> Memo<K, V> {
+    pub fn new() -> Self {
+        Memo {
+            cache: HashMap::new(),
+            limit: None,
+            hits: 0,
+            misses: 0,
+            skipped: 0,
+        }
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        Memo {
+            limit: Some(limit),
+            ..Self::new()
+        }
+    }
+
+    /// looks up a cached result - counts as a hit or a miss
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.cache.get(key) {
+            Some(value) => {
+                self.hits += 1;
+                Some(value)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// stores a result unless the size limit has been reached - returns whether it was stored
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let is_full = self.limit.is_some_and(|limit| self.cache.len() >= limit);
+        if is_full && !self.cache.contains_key(&key) {
+            self.skipped += 1;
+            return false;
+        }
+        self.cache.insert(key, value);
+        true
+    }
+
+    /// returns the cached result for the key or computes it with f - f gets the memo back so it can
+    /// recurse through it
+    pub fn cached(&mut self, key: K, f: impl FnOnce(&mut Self, &K) -> V) -> V
+    where
+        V: Clone,
+    {
+        if let Some(value) = self.get(&key) {
+            return value.clone();
+        }
+        let value = f(self, &key);
+        self.insert(key, value.clone());
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        MemoStats {
+            entries: self.cache.len(),
+            hits: self.hits,
+            misses: self.misses,
+            skipped: self.skipped,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} entries, {} hits, {} misses ({:.1}% hit rate), {} skipped",
+            self.entries,
+            self.hits,
+            self.misses,
+            100.0 * self.hit_rate(),
+            self.skipped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::memo::Memo;
+
+    fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        memo.cached(n, |memo, &n| {
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1, memo) + fib(n - 2, memo)
+            }
+        })
+    }
+
+    #[test]
+    fn memo_test() {
+        let mut memo = Memo::new();
+        assert_eq!(fib(80, &mut memo), 23416728348467685);
+        let stats = memo.stats();
+        assert_eq!((stats.entries, stats.misses, stats.hits), (81, 81, 78));
+
+        // results beyond the limit are still correct, they just aren't stored
+        let mut limited = Memo::with_limit(10);
+        assert_eq!(fib(30, &mut limited), 832040);
+        assert_eq!(limited.len(), 10);
+        assert!(limited.stats().skipped > 0);
+    }
+}
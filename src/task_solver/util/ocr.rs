@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// glyphs are separated by a column of unlit pixels
+const SPACING: usize = 1;
+
+/// letters of the font that Advent of Code puzzles draw as ascii art
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// turns six rows of letter art into text - '#' is a lit pixel, anything else is unlit, and blank
+/// glyphs become spaces
+pub fn recognize(art: &str) -> Result<String> {
+    let rows: Vec<Vec<bool>> = art
+        .lines()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(anyhow!(
+            "letter art must be {} rows high, not {}",
+            GLYPH_HEIGHT,
+            rows.len()
+        ));
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let pixel = |row: usize, col: usize| rows[row].get(col).copied().unwrap_or(false);
+
+    (0..width)
+        .step_by(GLYPH_WIDTH + SPACING)
+        .enumerate()
+        .map(|(i, left)| {
+            let glyph: Vec<String> = (0..GLYPH_HEIGHT)
+                .map(|row| {
+                    (left..left + GLYPH_WIDTH)
+                        .map(|col| if pixel(row, col) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            let spacing_lit = (0..GLYPH_HEIGHT).any(|row| pixel(row, left + GLYPH_WIDTH));
+            match glyph_char(&glyph) {
+                Some(c) if !spacing_lit => Ok(c),
+                _ => Err(anyhow!(
+                    "unknown glyph {} at column {}:\n{}",
+                    i + 1,
+                    left,
+                    glyph.join("\n")
+                )),
+            }
+        })
+        .collect()
+}
+
+fn glyph_char(glyph: &[String]) -> Option<char> {
+    if glyph.iter().all(|row| !row.contains('#')) {
+        return Some(' ');
+    }
+    FONT.iter()
+        .find(|(_, rows)| rows.iter().zip(glyph).all(|(a, b)| a == b))
+        .map(|&(c, _)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::ocr;
+
+    #[test]
+    fn recognize() {
+        let art = [
+            "####.#..#.###..###..####.####..##..#....",
+            "...#.#..#.#..#.#..#.#....#....#..#.#....",
+            "..#..#..#.#..#.#..#.###..###..#....#....",
+            ".#...#..#.###..###..#....#....#....#....",
+            "#....#..#.#....#.#..#....#....#..#.#....",
+            "####..##..#....#..#.#....####..##..####.",
+        ]
+        .join("\n");
+        assert_eq!(ocr::recognize(&art).unwrap(), "ZUPRFECL");
+        // the spacing column after the last glyph may be missing
+        assert_eq!(
+            ocr::recognize(&art.replace(".\n", "\n")).unwrap(),
+            "ZUPRFECL"
+        );
+
+        let error = ocr::recognize(&art.replacen("####", "###.", 1)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown glyph 1 at column 0:\n###.\n...#\n..#.\n.#..\n#...\n####"
+        );
+        assert!(ocr::recognize("#..#\n####").is_err());
+    }
+}
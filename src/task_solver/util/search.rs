@@ -0,0 +1,244 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+use num::Zero;
+
+/// A path found by one of the searches - nodes are listed from source to target
+#[derive(Debug)]
+pub struct Path<N, C> {
+    pub cost: C,
+    pub nodes: Vec<N>,
+}
+
+/// walks the chain of predecessors back from the target node
+fn reconstruct_path<N, K, FK>(target: N, parents: &HashMap<K, Option<N>>, key: &mut FK) -> Vec<N>
+where
+    N: Clone,
+    K: Hash + Eq,
+    FK: FnMut(&N) -> K,
+{
+    let mut nodes = vec![target];
+    while let Some(Some(parent)) = parents.get(&key(nodes.last().unwrap())) {
+        nodes.push(parent.clone());
+    }
+    nodes.reverse();
+    nodes
+}
+
+/// breadth-first search from any of the sources to the closest node that satisfies is_target - nodes
+/// with the same key are only visited once
+pub fn bfs<N, K, I, FN, FK, FT>(
+    sources: impl IntoIterator<Item = N>,
+    mut neighbours: FN,
+    mut key: FK,
+    mut is_target: FT,
+) -> Option<Path<N, usize>>
+where
+    N: Clone,
+    K: Hash + Eq,
+    I: IntoIterator<Item = N>,
+    FN: FnMut(&N) -> I,
+    FK: FnMut(&N) -> K,
+    FT: FnMut(&N) -> bool,
+{
+    let mut parents = HashMap::new();
+    let mut to_visit = VecDeque::new();
+    for source in sources {
+        if parents.insert(key(&source), None).is_none() {
+            to_visit.push_back((source, 0));
+        }
+    }
+
+    while let Some((node, depth)) = to_visit.pop_front() {
+        if is_target(&node) {
+            return Some(Path {
+                cost: depth,
+                nodes: reconstruct_path(node, &parents, &mut key),
+            });
+        }
+        for next in neighbours(&node) {
+            if let Entry::Vacant(entry) = parents.entry(key(&next)) {
+                entry.insert(Some(node.clone()));
+                to_visit.push_back((next, depth + 1));
+            }
+        }
+    }
+    None
+}
+
+/// visits every node reachable from the sources - returns the nodes in the order they were visited
+pub fn reachable<N, K, I, FN, FK>(
+    sources: impl IntoIterator<Item = N>,
+    mut neighbours: FN,
+    mut key: FK,
+) -> Vec<N>
+where
+    K: Hash + Eq,
+    I: IntoIterator<Item = N>,
+    FN: FnMut(&N) -> I,
+    FK: FnMut(&N) -> K,
+{
+    let mut seen = HashSet::new();
+    let mut to_visit = VecDeque::new();
+    for source in sources {
+        if seen.insert(key(&source)) {
+            to_visit.push_back(source);
+        }
+    }
+
+    let mut visited = Vec::new();
+    while let Some(node) = to_visit.pop_front() {
+        for next in neighbours(&node) {
+            if seen.insert(key(&next)) {
+                to_visit.push_back(next);
+            }
+        }
+        visited.push(node);
+    }
+    visited
+}
+
+/// Dijkstra's algorithm from any of the sources to the cheapest node that satisfies is_target
+pub fn dijkstra<N, K, C, I, FN, FK, FT>(
+    sources: impl IntoIterator<Item = N>,
+    neighbours: FN,
+    key: FK,
+    is_target: FT,
+) -> Option<Path<N, C>>
+where
+    N: Clone,
+    K: Hash + Eq,
+    C: Copy + Ord + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (N, C)>,
+    FN: FnMut(&N) -> I,
+    FK: FnMut(&N) -> K,
+    FT: FnMut(&N) -> bool,
+{
+    astar(sources, neighbours, key, |_| C::zero(), is_target)
+}
+
+/// A* search from any of the sources to the cheapest node that satisfies is_target - the heuristic
+/// must never overestimate the remaining cost to the closest target
+pub fn astar<N, K, C, I, FN, FK, FH, FT>(
+    sources: impl IntoIterator<Item = N>,
+    mut neighbours: FN,
+    mut key: FK,
+    mut heuristic: FH,
+    mut is_target: FT,
+) -> Option<Path<N, C>>
+where
+    N: Clone,
+    K: Hash + Eq,
+    C: Copy + Ord + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (N, C)>,
+    FN: FnMut(&N) -> I,
+    FK: FnMut(&N) -> K,
+    FH: FnMut(&N) -> C,
+    FT: FnMut(&N) -> bool,
+{
+    // nodes aren't required to be ordered --> the heap only stores indices into the list of discovered nodes
+    let mut discovered = Vec::new();
+    let mut to_visit = BinaryHeap::new();
+    let mut best = HashMap::new(); // { key -> (lowest known cost, predecessor) }
+
+    for source in sources {
+        if best.insert(key(&source), (C::zero(), None)).is_none() {
+            to_visit.push(Reverse((heuristic(&source), discovered.len())));
+            discovered.push((source, C::zero()));
+        }
+    }
+
+    while let Some(Reverse((_, i))) = to_visit.pop() {
+        let (node, cost) = discovered[i].clone();
+        if best[&key(&node)].0 < cost {
+            continue; // found a cheaper way to this node after it had been queued
+        }
+        if is_target(&node) {
+            let parents = best
+                .into_iter()
+                .map(|(k, (_, parent))| (k, parent))
+                .collect();
+            return Some(Path {
+                cost,
+                nodes: reconstruct_path(node, &parents, &mut key),
+            });
+        }
+        for (next, step_cost) in neighbours(&node) {
+            let next_cost = cost + step_cost;
+            let is_improvement = match best.entry(key(&next)) {
+                Entry::Occupied(mut entry) if next_cost < entry.get().0 => {
+                    entry.insert((next_cost, Some(node.clone())));
+                    true
+                }
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert((next_cost, Some(node.clone())));
+                    true
+                }
+            };
+            if is_improvement {
+                to_visit.push(Reverse((next_cost + heuristic(&next), discovered.len())));
+                discovered.push((next, next_cost));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::search::{astar, bfs, dijkstra, reachable};
+
+    /// 5x5 grid with a wall in column 2 that can only be passed in the bottom row
+    fn moves(&(x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| (0..5).contains(&x) && (0..5).contains(&y) && (y != 2 || x == 4))
+            .collect()
+    }
+
+    #[test]
+    fn bfs_test() {
+        let path = bfs([(0, 0)], moves, |p| *p, |p| *p == (0, 4)).unwrap();
+        assert_eq!(path.cost, 12);
+        assert_eq!(path.nodes.len(), 13);
+        assert_eq!(path.nodes.first(), Some(&(0, 0)));
+        assert_eq!(path.nodes.last(), Some(&(0, 4)));
+
+        // multiple sources and targets
+        let path = bfs([(0, 0), (3, 1)], moves, |p| *p, |&(_, y)| y == 3).unwrap();
+        assert_eq!((path.cost, path.nodes[0]), (3, (3, 1)));
+
+        assert!(bfs([(0, 0)], moves, |p| *p, |p| *p == (9, 9)).is_none());
+        assert_eq!(reachable([(0, 0)], moves, |p| *p).len(), 21);
+    }
+
+    #[test]
+    fn weighted_test() {
+        // moving right is twice as expensive as any other move
+        let weighted_moves = |p: &(i32, i32)| {
+            moves(p)
+                .into_iter()
+                .map(|n| (n, if n.1 > p.1 { 2u32 } else { 1 }))
+                .collect::<Vec<_>>()
+        };
+        let path = dijkstra([(0, 0)], weighted_moves, |p| *p, |p| *p == (0, 4)).unwrap();
+        assert_eq!(path.cost, 16);
+
+        let manhattan = |p: &(i32, i32)| p.0.abs_diff(0) + p.1.abs_diff(4);
+        let path = astar(
+            [(0, 0)],
+            weighted_moves,
+            |p| *p,
+            manhattan,
+            |p| *p == (0, 4),
+        )
+        .unwrap();
+        assert_eq!(path.cost, 16);
+        assert_eq!(path.nodes.len(), 13);
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    cmp::Ordering,
+    iter::{Product, Sum},
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+/// Keeps the k largest items seen so far - items are stored as a min-heap, so the smallest kept item
+/// can be replaced in O(log k)
+#[derive(Clone, Debug)]
+pub struct TopK<T, F = fn(&T, &T) -> Ordering> {
+    k: usize,
+    heap: Vec<T>,
+    cmp: F,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self::by(k, T::cmp)
+    }
+
+    /// the k largest of the items
+    pub fn of(k: usize, items: impl IntoIterator<Item = T>) -> Self {
+        let mut top_k = Self::new(k);
+        top_k.extend(items);
+        top_k
+    }
+}
+
+impl<T> TopK<T> {
+    /// orders items by the key instead of the items themselves
+    pub fn by_key<U: Ord>(k: usize, key: impl Fn(&T) -> U) -> TopK<T, impl Fn(&T, &T) -> Ordering> {
+        TopK::by(k, move |a: &T, b: &T| key(a).cmp(&key(b)))
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> TopK<T, F> {
+    /// orders items by the comparator - the largest items according to cmp are kept
+    pub fn by(k: usize, cmp: F) -> Self {
+        TopK {
+            k,
+            // k may be far larger than the number of items that ever get inserted
+            heap: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// maximum number of kept items
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// inserts an item - returns the item that didn't make it into the top k, if any
+    pub fn insert(&mut self, item: T) -> Option<T> {
+        if self.heap.len() < self.k {
+            self.heap.push(item);
+            self.sift_up(self.heap.len() - 1);
+            None
+        } else if self.k > 0 && (self.cmp)(&item, &self.heap[0]) == Ordering::Greater {
+            let dropped = std::mem::replace(&mut self.heap[0], item);
+            self.sift_down(0);
+            Some(dropped)
+        } else {
+            Some(item)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// smallest of the kept items - any new item has to beat it once the collection is full
+    pub fn min(&self) -> Option<&T> {
+        self.heap.first()
+    }
+
+    /// iterates over the kept items in no particular order
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    /// kept items, largest first
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let cmp = self.cmp;
+        let mut items = self.heap;
+        items.sort_by(|a, b| cmp(b, a));
+        items
+    }
+
+    pub fn sum<'a, S: Sum<&'a T>>(&'a self) -> S {
+        self.heap.iter().sum()
+    }
+
+    pub fn product<'a, P: Product<&'a T>>(&'a self) -> P {
+        self.heap.iter().product()
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if (self.cmp)(&self.heap[i], &self.heap[parent]) != Ordering::Less {
+                break;
+            }
+            self.heap.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let mut smallest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.heap.len()
+                    && (self.cmp)(&self.heap[child], &self.heap[smallest]) == Ordering::Less
+                {
+                    smallest = child;
+                }
+            }
+            if smallest == i {
+                break;
+            }
+            self.heap.swap(i, smallest);
+            i = smallest;
+        }
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> Extend<T> for TopK<T, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<'a, T, F> IntoIterator for &'a TopK<T, F> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.heap.iter()
+    }
+}
+
+/// A TopK whose k is fixed at compile time, so that it can be collected from an iterator
+#[derive(Clone, Debug)]
+pub struct FixedTopK<T, const K: usize>(TopK<T>);
+
+impl<T: Ord, const K: usize> FixedTopK<T, K> {
+    pub fn new() -> Self {
+        FixedTopK(TopK::new(K))
+    }
+
+    pub fn into_inner(self) -> TopK<T> {
+        self.0
+    }
+}
+
+impl<T: Ord, const K: usize> Default for FixedTopK<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const K: usize> Deref for FixedTopK<T, K> {
+    type Target = TopK<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const K: usize> DerefMut for FixedTopK<T, K> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Ord, const K: usize> FromIterator<T> for FixedTopK<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut top_k = Self::new();
+        top_k.extend(iter);
+        top_k
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task_solver::util::top_k::{FixedTopK, TopK};
+
+    #[test]
+    fn top_k_test() {
+        let top = TopK::of(3, [5, 1, 9, 7, 3, 9, 2]);
+        assert_eq!(top.k(), 3);
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.min(), Some(&7));
+        assert_eq!(top.sum::<u32>(), 25);
+        assert_eq!(top.product::<u32>(), 567);
+        assert_eq!(top.into_sorted_vec(), vec![9, 9, 7]);
+
+        let collected: FixedTopK<u32, 2> = [5, 1, 9, 7].into_iter().collect();
+        assert_eq!((collected.k(), collected.sum::<u32>()), (2, 16));
+        assert_eq!(collected.into_inner().into_sorted_vec(), vec![9, 7]);
+
+        let mut empty = TopK::<u32>::new(0);
+        assert_eq!(empty.insert(1), Some(1));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn top_k_by_key_test() {
+        // keep the elves with the fewest calories
+        let mut top = TopK::<(usize, u32)>::by_key(2, |&(_, cals)| std::cmp::Reverse(cals));
+        assert_eq!(top.insert((0, 300)), None);
+        assert_eq!(top.insert((1, 100)), None);
+        assert_eq!(top.insert((2, 200)), Some((0, 300)));
+        assert_eq!(top.insert((3, 400)), Some((3, 400)));
+        assert_eq!(top.into_sorted_vec(), vec![(1, 100), (2, 200)]);
+    }
+}
@@ -0,0 +1,179 @@
+use aoc_2022::task_solver::{self, day_1, day_10, day_15};
+
+/// path of the example input given in the puzzle description of the day
+fn sample(day: u8) -> String {
+    format!(
+        "{}/input/samples/task_{}.input",
+        env!("CARGO_MANIFEST_DIR"),
+        day
+    )
+}
+
+fn assert_answers(day: u8, expected: [&str; 2]) {
+    for (task, expected) in (1..=2).zip(expected) {
+        let answer = task_solver::solve_task(day, task, sample(day))
+            .unwrap_or_else(|e| panic!("day {} task {} failed: {:?}", day, task, e));
+        assert_eq!(
+            answer, expected,
+            "wrong answer for day {} task {}",
+            day, task
+        );
+    }
+}
+
+#[test]
+fn day_1() {
+    assert_answers(1, ["24000", "45000"]);
+    let report = day_1::report(sample(1), 3, 10000, 45000).unwrap();
+    assert!(report.contains("top 3: elf 3 (24000), elf 2 (11000), elf 4 (10000)"));
+    assert!(report.ends_with("elves needed to carry 45000 calories: 3"));
+
+    // task 3 reports on the inventory, with half of all calories as target
+    let report = task_solver::solve_task(1, 3, sample(1)).unwrap();
+    let expected = [
+        "5 elves carrying 10 items - median total: 10000, 90th percentile: 24000",
+        "top 3: elf 3 (24000), elf 2 (11000), elf 4 (10000)",
+        "histogram of totals per 10000 calories:",
+        "         0: 2",
+        "     10000: 2",
+        "     20000: 1",
+        "elves needed to carry 27500 calories: 2",
+    ];
+    assert_eq!(report, expected.join("\n"));
+}
+
+#[test]
+fn day_2() {
+    assert_answers(2, ["15", "12"]);
+}
+
+#[test]
+fn day_3() {
+    assert_answers(3, ["157", "70"]);
+}
+
+#[test]
+fn day_4() {
+    assert_answers(4, ["2", "4"]);
+}
+
+#[test]
+fn day_5() {
+    assert_answers(5, ["CMZ", "MCD"]);
+}
+
+#[test]
+fn day_6() {
+    assert_answers(6, ["7", "29"]);
+}
+
+#[test]
+fn day_7() {
+    assert_answers(7, ["95437", "24933642"]);
+}
+
+#[test]
+fn day_8() {
+    assert_answers(8, ["21", "8"]);
+}
+
+#[test]
+fn day_9() {
+    assert_answers(9, ["13", "1"]);
+}
+
+#[test]
+fn day_10() {
+    let crt = [
+        "##..##..##..##..##..##..##..##..##..##..",
+        "###...###...###...###...###...###...###.",
+        "####....####....####....####....####....",
+        "#####.....#####.....#####.....#####.....",
+        "######......######......######......####",
+        "#######.......#######.......#######.....",
+    ]
+    .join("\n");
+    assert_eq!(task_solver::solve_task(10, 1, sample(10)).unwrap(), "13140");
+    // the example doesn't draw letters, so check the pixels instead of the recognized text
+    let program = day_10::Decoder::default().decode_input(sample(10)).unwrap();
+    assert_eq!(day_10::draw_crt(program).unwrap(), crt);
+}
+
+#[test]
+fn day_11() {
+    assert_answers(11, ["10605", "2713310158"]);
+}
+
+#[test]
+fn day_12() {
+    assert_answers(12, ["31", "29"]);
+}
+
+#[test]
+fn day_13() {
+    assert_answers(13, ["13", "140"]);
+}
+
+#[test]
+fn day_14() {
+    assert_answers(14, ["24", "93"]);
+}
+
+#[test]
+fn day_15() {
+    // the example asks about row 10 and a search range of 0..=20
+    let answer = day_15::solve_with(1, sample(15), 10, (0, 20)).unwrap();
+    assert_eq!(answer, "26");
+    let answer = day_15::solve_with(2, sample(15), 10, (0, 20)).unwrap();
+    assert_eq!(answer, "56000011");
+}
+
+#[test]
+fn day_16() {
+    assert_answers(16, ["1651", "1707"]);
+}
+
+#[test]
+fn day_17() {
+    assert_answers(17, ["3068", "1514285714288"]);
+}
+
+#[test]
+fn day_18() {
+    assert_answers(18, ["64", "58"]);
+}
+
+#[test]
+fn day_19() {
+    assert_answers(19, ["33", "3472"]);
+}
+
+#[test]
+fn day_20() {
+    assert_answers(20, ["3", "1623178306"]);
+}
+
+#[test]
+fn day_21() {
+    assert_answers(21, ["152", "301"]);
+}
+
+#[test]
+fn day_22() {
+    assert_answers(22, ["6032", "5031"]);
+}
+
+#[test]
+fn day_23() {
+    assert_answers(23, ["110", "20"]);
+}
+
+#[test]
+fn day_24() {
+    assert_answers(24, ["18", "54"]);
+}
+
+#[test]
+fn day_25() {
+    assert_answers(25, ["2=-1=0", "2=-1=0"]);
+}